@@ -4,11 +4,66 @@ extern crate test;
 #[macro_use]
 extern crate serde_derive;
 
-use serde::Serialize;
-use serde_bencode::de::from_bytes;
+use serde::{Deserialize, Serialize};
+use serde_bencode::de::{from_bytes, Deserializer};
 use serde_bencode::ser::Serializer;
+use serde_bencode::value::Value;
+use serde_bytes::ByteBuf;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
 use test::Bencher;
 
+const FIXTURES: [&[u8]; 2] = [
+    include_bytes!("../tests/fixtures/torrents/with-one-node.torrent"),
+    include_bytes!("../tests/fixtures/torrents/with-two-nodes.torrent"),
+];
+
+const FIXTURE_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/torrents/with-two-nodes.torrent"
+);
+
+#[derive(Serialize, Deserialize)]
+struct Torrent {
+    info: Info,
+    #[serde(default)]
+    nodes: Option<Vec<(String, i64)>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Info {
+    #[serde(default)]
+    length: Option<i64>,
+    name: String,
+    #[serde(rename = "piece length")]
+    piece_length: i64,
+    pieces: ByteBuf,
+}
+
+/// A torrent with many files, so that most of the input is integers and short byte strings.
+fn multi_file_torrent() -> Vec<u8> {
+    let files: Vec<Value> = (0..1000_i64)
+        .map(|i| {
+            let mut file = std::collections::BTreeMap::new();
+            file.insert(b"length".to_vec(), Value::Int(i * 1_048_583));
+            file.insert(
+                b"path".to_vec(),
+                Value::List(vec![
+                    format!("dir{}", i % 10).into(),
+                    format!("{i}.bin").into(),
+                ]),
+            );
+            Value::Dict(file)
+        })
+        .collect();
+    let mut info = std::collections::BTreeMap::new();
+    info.insert(b"files".to_vec(), Value::List(files));
+    info.insert(b"name".to_vec(), "multi".into());
+    info.insert(b"piece length".to_vec(), Value::Int(262_144));
+    serde_bencode::to_bytes(&Value::Dict(info)).unwrap()
+}
+
 #[bench]
 fn ser_de_simple(b: &mut Bencher) {
     #[derive(Serialize, Deserialize)]
@@ -51,3 +106,83 @@ fn ser_de_nested(b: &mut Bencher) {
         b
     });
 }
+
+#[bench]
+fn de_torrent_fixtures_from_bytes(b: &mut Bencher) {
+    b.iter(|| {
+        for fixture in FIXTURES {
+            let torrent: Torrent = from_bytes(fixture).unwrap();
+            test::black_box(torrent);
+        }
+    });
+}
+
+#[bench]
+fn de_torrent_fixtures_from_reader(b: &mut Bencher) {
+    b.iter(|| {
+        for fixture in FIXTURES {
+            let torrent = Torrent::deserialize(&mut Deserializer::new(fixture)).unwrap();
+            test::black_box(torrent);
+        }
+    });
+}
+
+#[bench]
+fn de_torrent_fixture_from_file(b: &mut Bencher) {
+    b.iter(|| {
+        let file = File::open(FIXTURE_PATH).unwrap();
+        Torrent::deserialize(&mut Deserializer::from_buf_reader(BufReader::new(file))).unwrap()
+    });
+}
+
+#[bench]
+fn de_torrent_fixture_from_unbuffered_file(b: &mut Bencher) {
+    b.iter(|| {
+        let file = File::open(FIXTURE_PATH).unwrap();
+        Torrent::deserialize(&mut Deserializer::new(file)).unwrap()
+    });
+}
+
+#[bench]
+fn de_multi_file_torrent_from_bytes(b: &mut Bencher) {
+    let torrent = multi_file_torrent();
+    b.bytes = torrent.len() as u64;
+    b.iter(|| from_bytes::<Value>(&torrent).unwrap());
+}
+
+#[bench]
+fn de_multi_file_torrent_from_reader(b: &mut Bencher) {
+    let torrent = multi_file_torrent();
+    b.bytes = torrent.len() as u64;
+    b.iter(|| Value::deserialize(&mut Deserializer::new(torrent.as_slice())).unwrap());
+}
+
+/// Writes the multi-file torrent to a temporary file, returning its path and length.
+fn multi_file_torrent_file(name: &str) -> (PathBuf, u64) {
+    let torrent = multi_file_torrent();
+    let path = std::env::temp_dir().join(format!("serde-bencode-{}-{name}", std::process::id()));
+    std::fs::write(&path, &torrent).unwrap();
+    (path, torrent.len() as u64)
+}
+
+#[bench]
+fn de_multi_file_torrent_from_file(b: &mut Bencher) {
+    let (path, len) = multi_file_torrent_file("buffered");
+    b.bytes = len;
+    b.iter(|| {
+        let file = BufReader::new(File::open(&path).unwrap());
+        Value::deserialize(&mut Deserializer::from_buf_reader(file)).unwrap()
+    });
+    std::fs::remove_file(path).unwrap();
+}
+
+#[bench]
+fn de_multi_file_torrent_from_unbuffered_file(b: &mut Bencher) {
+    let (path, len) = multi_file_torrent_file("unbuffered");
+    b.bytes = len;
+    b.iter(|| {
+        let file = File::open(&path).unwrap();
+        Value::deserialize(&mut Deserializer::new(file)).unwrap()
+    });
+    std::fs::remove_file(path).unwrap();
+}
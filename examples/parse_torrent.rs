@@ -5,6 +5,7 @@ use serde_bencode::de;
 use serde_bytes::ByteBuf;
 use std::io::{self, Read};
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct Node(String, i64);
 
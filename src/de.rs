@@ -1,40 +1,74 @@
 //! Deserialize bencode data to a Rust data structure
 
+pub mod read;
+pub(crate) mod tokenizer;
+
+pub use self::tokenizer::{Token, Tokenizer};
+
+use self::read::{BufIoRead, IoRead, Read, SliceRead};
 use crate::error::{Error, Result};
+use crate::policy::{self, EnumPolicy, FloatPolicy, NonePolicy};
+use crate::raw;
+use crate::value::{BigInt, Value};
 use serde::{
-    de::{self, Error as _, Unexpected},
+    de::{self, Deserialize as _, Error as _, Unexpected},
     forward_to_deserialize_any,
 };
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
-use std::io::Read;
+use std::io;
+use std::marker::PhantomData;
 use std::str;
 
 #[doc(hidden)]
 // todo: This should be pub(crate).
-pub struct BencodeAccess<'a, R: Read> {
+pub struct BencodeAccess<'a, 'de, R> {
     de: &'a mut Deserializer<R>,
     len: Option<usize>,
+    /// Number of list elements or dictionary entries visited so far.
+    index: usize,
+    /// The most recent dictionary key.
+    key: Option<Cow<'de, [u8]>>,
+    /// A dictionary key whose entry is left out.
+    skip_key: Option<&'static str>,
 }
 
-impl<'a, R: 'a + Read> BencodeAccess<'a, R> {
-    fn new(de: &'a mut Deserializer<R>, len: Option<usize>) -> BencodeAccess<'a, R> {
-        BencodeAccess { de, len }
+impl<'a, R> BencodeAccess<'a, '_, R> {
+    fn new(de: &'a mut Deserializer<R>, len: Option<usize>) -> Self {
+        BencodeAccess {
+            de,
+            len,
+            index: 0,
+            key: None,
+            skip_key: None,
+        }
     }
 }
 
-impl<'de, 'a, R: 'a + Read> de::SeqAccess<'de> for BencodeAccess<'a, R> {
+impl<R> BencodeAccess<'_, '_, R> {
+    fn check_elements(&self) -> Result<()> {
+        self.de.check_elements(self.index)
+    }
+}
+
+impl<'de, 'a, R: 'a + Read<'de>> de::SeqAccess<'de> for BencodeAccess<'a, 'de, R> {
     type Error = Error;
 
     fn next_element_seed<T: de::DeserializeSeed<'de>>(
         &mut self,
         seed: T,
     ) -> Result<Option<T::Value>> {
-        let res = match self.de.parse()? {
-            ParseResult::End => Ok(None),
-            r => {
-                self.de.next = Some(r);
-                Ok(Some(seed.deserialize(&mut *self.de)?))
-            }
+        let res = if self.de.peek_end()? {
+            None
+        } else {
+            self.check_elements()?;
+            let value = seed
+                .deserialize(&mut *self.de)
+                .map_err(|e| e.in_element(self.index, self.de.read.byte_offset()))?;
+            self.index += 1;
+            Some(value)
         };
         if let Some(l) = self.len {
             let l = l - 1;
@@ -43,34 +77,74 @@ impl<'de, 'a, R: 'a + Read> de::SeqAccess<'de> for BencodeAccess<'a, R> {
                 return Err(Error::InvalidType("expected `e`".to_string()));
             }
         }
-        res
+        Ok(res)
     }
 }
 
-impl<'de, 'a, R: 'a + Read> de::MapAccess<'de> for BencodeAccess<'a, R> {
+impl<'de, 'a, R: 'a + Read<'de>> de::MapAccess<'de> for BencodeAccess<'a, 'de, R> {
     type Error = Error;
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
     where
         K: de::DeserializeSeed<'de>,
     {
-        match self.de.parse()? {
-            ParseResult::End => Ok(None),
-            r => {
-                self.de.next = Some(r);
-                Ok(Some(seed.deserialize(&mut *self.de)?))
+        if self.de.peek_end()? {
+            return Ok(None);
+        }
+        self.check_elements()?;
+        self.index += 1;
+
+        let key = match self.de.parse()? {
+            ParseResult::Bytes(key) => key,
+            r => return Err(r.to_unexpected_error("bytes")),
+        };
+        let offset = self.de.read.byte_offset();
+        if self.skip_key.map(str::as_bytes) == Some(key.as_ref()) {
+            de::IgnoredAny::deserialize(&mut *self.de).map_err(|e| e.in_key(&key, offset))?;
+            self.key = Some(key);
+            return self.next_key_seed(seed);
+        }
+        if self.de.canonical {
+            if let Some(last_key) = &self.key {
+                match last_key.as_ref().cmp(&key) {
+                    Ordering::Less => {}
+                    Ordering::Equal => {
+                        return Err(Error::DuplicateKey(format!(
+                            "Duplicate Key: `{}`",
+                            String::from_utf8_lossy(&key)
+                        ))
+                        .in_key(&key, offset))
+                    }
+                    Ordering::Greater => {
+                        return Err(Error::UnsortedKey(format!(
+                            "Unsorted Key: `{}` (must sort after `{}`)",
+                            String::from_utf8_lossy(&key),
+                            String::from_utf8_lossy(last_key)
+                        ))
+                        .in_key(&key, offset))
+                    }
+                }
             }
         }
+
+        let key = self.key.insert(key);
+        seed.deserialize(MapKeyDeserializer { key })
+            .map(Some)
+            .map_err(|e| e.in_key(key, offset))
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
         V: de::DeserializeSeed<'de>,
     {
-        seed.deserialize(&mut *self.de)
+        self.de.map_value = true;
+        seed.deserialize(&mut *self.de).map_err(|e| {
+            let key = self.key.as_deref().unwrap_or_default();
+            e.in_key(key, self.de.read.byte_offset())
+        })
     }
 }
 
-impl<'de, 'a, R: 'a + Read> de::VariantAccess<'de> for BencodeAccess<'a, R> {
+impl<'de, 'a, R: 'a + Read<'de>> de::VariantAccess<'de> for BencodeAccess<'a, 'de, R> {
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
@@ -109,27 +183,60 @@ impl<'de, 'a, R: 'a + Read> de::VariantAccess<'de> for BencodeAccess<'a, R> {
     }
 }
 
-impl<'de, 'a, R: 'a + Read> de::EnumAccess<'de> for BencodeAccess<'a, R> {
+impl<'de, R: Read<'de>> BencodeAccess<'_, 'de, R> {
+    /// Deserializes the variant from the byte string holding its name, or its index with
+    /// [`EnumPolicy::VariantIndex`].
+    fn variant_name<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        if self.de.enums != EnumPolicy::VariantIndex {
+            return seed.deserialize(&mut *self.de);
+        }
+        let key = match self.de.parse()? {
+            ParseResult::Bytes(key) => key,
+            r => return Err(r.to_unexpected_error("bytes")),
+        };
+        let index: u32 = MapKeyDeserializer { key: &key }.parse("a variant index")?;
+        seed.deserialize(de::IntoDeserializer::<Error>::into_deserializer(index))
+    }
+}
+
+impl<'de, 'a, R: 'a + Read<'de>> de::EnumAccess<'de> for BencodeAccess<'a, 'de, R> {
     type Error = Error;
     type Variant = Self;
-    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
-        match self.de.parse()? {
-            t @ ParseResult::Bytes(_) => {
-                self.de.next = Some(t);
-                Ok((seed.deserialize(&mut *self.de)?, self))
+    fn variant_seed<V: de::DeserializeSeed<'de>>(mut self, seed: V) -> Result<(V::Value, Self)> {
+        match self.de.read.peek()? {
+            Some(b'0'..=b'9') => Ok((self.variant_name(seed)?, self)),
+            Some(b'd') => {
+                self.de.parse()?;
+                Ok((self.variant_name(seed)?, self))
+            }
+            Some(b'i') if self.de.enums == EnumPolicy::VariantIndex => {
+                let index = match self.de.parse()? {
+                    ParseResult::Int(index) => u32::try_from(index).map_err(|_| {
+                        Error::invalid_value(Unexpected::Signed(index), &"a variant index")
+                    })?,
+                    r => return Err(r.to_unexpected_error("a variant index")),
+                };
+                let variant =
+                    seed.deserialize(de::IntoDeserializer::<Error>::into_deserializer(index))?;
+                Ok((variant, self))
+            }
+            _ => {
+                let t = self.de.parse()?;
+                Err(Error::InvalidValue(format!(
+                    "Expected bytes or map; got `{t:?}`"
+                )))
             }
-            ParseResult::Map => Ok((seed.deserialize(&mut *self.de)?, self)),
-            t => Err(Error::InvalidValue(format!(
-                "Expected bytes or map; got `{t:?}`"
-            ))),
         }
     }
 }
 
 #[derive(Debug, Eq, PartialEq)]
-enum ParseResult {
+enum ParseResult<'de> {
     Int(i64),
-    Bytes(Vec<u8>),
+    /// integer that does not fit in an `i64`
+    BigInt(BigInt),
+    /// byte string, borrowed from the input when possible
+    Bytes(Cow<'de, [u8]>),
     /// list start
     List,
     /// map start
@@ -138,10 +245,11 @@ enum ParseResult {
     End,
 }
 
-impl ParseResult {
+impl ParseResult<'_> {
     fn to_unexpected_error(&self, expected: &str) -> Error {
         match self {
             Self::Int(i) => Error::invalid_type(Unexpected::Signed(*i), &expected),
+            Self::BigInt(_) => Error::invalid_type(Unexpected::Other("integer"), &expected),
             Self::Bytes(bytes) => Error::invalid_type(Unexpected::Bytes(bytes), &expected),
             Self::List => Error::invalid_type(Unexpected::Seq, &expected),
             Self::Map => Error::invalid_type(Unexpected::Map, &expected),
@@ -150,138 +258,1031 @@ impl ParseResult {
     }
 }
 
+/// Visits a byte string as a string if it is valid UTF-8, and as bytes otherwise.
+///
+/// Visitors that buffer the input, such as those serde uses for `#[serde(flatten)]` and
+/// internally tagged enums, can then hand the string on to `String`, `char` and enum fields as
+/// well as to byte fields.
+pub(crate) fn visit_bytes_or_str<'de, V: de::Visitor<'de>>(
+    bytes: Cow<'de, [u8]>,
+    visitor: V,
+) -> Result<V::Value> {
+    match bytes {
+        Cow::Borrowed(bytes) => match str::from_utf8(bytes) {
+            Ok(s) => visitor.visit_borrowed_str(s),
+            Err(_) => visitor.visit_borrowed_bytes(bytes),
+        },
+        Cow::Owned(bytes) => match String::from_utf8(bytes) {
+            Ok(s) => visitor.visit_string(s),
+            Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+        },
+    }
+}
+
+/// Gives access to an internally tagged enum variant: a dictionary whose entry under the tag
+/// names the variant, and whose other entries are its contents. The contents are deserialized
+/// from the dictionary again, with the same settings as the deserializer it was read with.
+struct TaggedAccess<R> {
+    de: Deserializer<R>,
+    tag: &'static str,
+    variant: Vec<u8>,
+    /// Offset of the dictionary in the input, which the offsets of errors are relative to.
+    offset: usize,
+}
+
+impl<'de, R: Read<'de>> de::EnumAccess<'de> for TaggedAccess<R> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<S: de::DeserializeSeed<'de>>(mut self, seed: S) -> Result<(S::Value, Self)> {
+        let key = Cow::Owned(std::mem::take(&mut self.variant));
+        let variant = seed.deserialize(MapKeyDeserializer { key: &key })?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, R: Read<'de>> de::VariantAccess<'de> for TaggedAccess<R> {
+    type Error = Error;
+
+    /// Any other entries are ignored.
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(mut self, seed: T) -> Result<T::Value> {
+        self.de.skip_key = Some(self.tag);
+        seed.deserialize(&mut self.de)
+            .map_err(|e| e.offset_by(self.offset))
+    }
+
+    fn tuple_variant<W: de::Visitor<'de>>(self, _len: usize, _visitor: W) -> Result<W::Value> {
+        Err(Error::invalid_type(Unexpected::Map, &"tuple variant"))
+    }
+
+    fn struct_variant<W: de::Visitor<'de>>(
+        mut self,
+        _fields: &'static [&'static str],
+        visitor: W,
+    ) -> Result<W::Value> {
+        self.de.skip_key = Some(self.tag);
+        de::Deserializer::deserialize_map(&mut self.de, visitor)
+            .map_err(|e| e.offset_by(self.offset))
+    }
+}
+
+/// Finds the name of the variant under the tag of an internally tagged enum, skipping the other
+/// entries of the dictionary.
+struct TagVisitor(&'static str);
+
+impl<'de> de::Visitor<'de> for TagVisitor {
+    type Value = Option<Vec<u8>>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("a dictionary")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut variant = None;
+        while let Some(key) = map.next_key::<serde_bytes::ByteBuf>()? {
+            if key.as_slice() == self.0.as_bytes() {
+                variant = Some(map.next_value::<serde_bytes::ByteBuf>()?.into_vec());
+            } else {
+                map.next_value::<de::IgnoredAny>()?;
+            }
+        }
+        Ok(variant)
+    }
+}
+
+/// Deserializer for dictionary keys that have already been read from the input.
+///
+/// Besides strings and bytes, keys are parsed as the decimal integers, chars, newtype structs
+/// and unit variants written by the key serializer.
+pub(crate) struct MapKeyDeserializer<'k, 'de> {
+    pub(crate) key: &'k Cow<'de, [u8]>,
+}
+
+impl MapKeyDeserializer<'_, '_> {
+    fn to_str(&self) -> Result<&str> {
+        str::from_utf8(self.key)
+            .map_err(|_| Error::invalid_value(Unexpected::Bytes(self.key), &"utf-8 string"))
+    }
+
+    fn parse<T: str::FromStr>(&self, expected: &str) -> Result<T> {
+        self.to_str()?
+            .parse()
+            .map_err(|_| Error::invalid_value(Unexpected::Bytes(self.key), &expected))
+    }
+}
+
+/// Implements the `deserialize_*` methods for the integer types, which parse the key.
+macro_rules! deserialize_integer_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(self.parse("an integer")?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKeyDeserializer<'_, 'de> {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match *self.key {
+            Cow::Borrowed(key) => visit_bytes_or_str(Cow::Borrowed(key), visitor),
+            Cow::Owned(ref key) => match str::from_utf8(key) {
+                Ok(s) => visitor.visit_str(s),
+                Err(_) => visitor.visit_bytes(key),
+            },
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 unit seq map unit_struct tuple_struct tuple ignored_any struct
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match *self.key {
+            Cow::Borrowed(key) => visitor.visit_borrowed_bytes(key),
+            Cow::Owned(ref key) => visitor.visit_bytes(key),
+        }
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    deserialize_integer_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+    }
+
+    fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_char(self.parse("a single character")?)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    #[inline]
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let utf8_error = |key: &[u8]| Error::invalid_value(Unexpected::Bytes(key), &"utf-8 string");
+        match *self.key {
+            Cow::Borrowed(key) => {
+                visitor.visit_borrowed_str(str::from_utf8(key).map_err(|_| utf8_error(key))?)
+            }
+            Cow::Owned(ref key) => {
+                visitor.visit_str(str::from_utf8(key).map_err(|_| utf8_error(key))?)
+            }
+        }
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    /// Only unit variants can be keys, as their name.
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_enum(de::IntoDeserializer::<Error>::into_deserializer(
+            self.to_str()?,
+        ))
+    }
+}
+
+/// Limits on the resources the deserializer may use, to safely decode untrusted input.
+///
+/// Exceeding any of them fails deserialization with [`Error::LimitExceeded`].
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::{DecodeLimits, Deserializer};
+///
+/// let limits = DecodeLimits {
+///     max_depth: 16,
+///     max_bytes_len: 1024,
+///     ..DecodeLimits::default()
+/// };
+/// let de = Deserializer::from_slice(b"le").limits(limits);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum number of nested lists and dictionaries.
+    pub max_depth: usize,
+
+    /// Maximum length of a single byte string.
+    pub max_bytes_len: usize,
+
+    /// Maximum number of elements in a single list, or entries in a single dictionary.
+    pub max_elements: usize,
+
+    /// Maximum number of bytes decoded in total for byte strings, and for integers of more than
+    /// 20 digits.
+    pub max_alloc: usize,
+}
+
+impl DecodeLimits {
+    /// Limits that never trigger. Deeply nested input may then overflow the stack.
+    #[must_use]
+    pub fn unlimited() -> Self {
+        DecodeLimits {
+            max_depth: usize::MAX,
+            max_bytes_len: usize::MAX,
+            max_elements: usize::MAX,
+            max_alloc: usize::MAX,
+        }
+    }
+}
+
+impl Default for DecodeLimits {
+    /// Limits the nesting depth to 128, which protects against stack overflows. Everything else
+    /// is unlimited.
+    fn default() -> Self {
+        DecodeLimits {
+            max_depth: 128,
+            ..DecodeLimits::unlimited()
+        }
+    }
+}
+
 /// A structure for deserializing bencode into Rust values.
+///
+/// Deserializing fails with an [`Error::Located`], which wraps the underlying error with the
+/// byte offset and key path at which decoding failed. Match on [`Error::inner`] to tell the
+/// errors apart.
 #[derive(Debug)]
-pub struct Deserializer<R: Read> {
-    reader: R,
-    next: Option<ParseResult>,
+pub struct Deserializer<R> {
+    read: R,
+    canonical: bool,
+    strict_bools: bool,
+    float: FloatPolicy,
+    none: NonePolicy,
+    enums: EnumPolicy,
+    /// Whether the value about to be deserialized is the value of a dictionary entry.
+    map_value: bool,
+    /// A key to leave out of the next dictionary, which holds the tag of an internally tagged
+    /// enum.
+    skip_key: Option<&'static str>,
+    limits: DecodeLimits,
+    depth: usize,
+    allocated: usize,
+}
+
+impl<R> Deserializer<R> {
+    fn with_read(read: R) -> Self {
+        Deserializer {
+            read,
+            canonical: false,
+            strict_bools: true,
+            float: FloatPolicy::default(),
+            none: NonePolicy::default(),
+            enums: EnumPolicy::default(),
+            map_value: false,
+            skip_key: None,
+            limits: DecodeLimits::default(),
+            depth: 0,
+            allocated: 0,
+        }
+    }
+
+    /// A deserializer with the same settings, reading a value nested in the one being
+    /// deserialized from `read`.
+    fn nested<S>(&self, read: S, allocated: usize) -> Deserializer<S> {
+        Deserializer {
+            read,
+            canonical: self.canonical,
+            strict_bools: self.strict_bools,
+            float: self.float,
+            none: self.none,
+            enums: self.enums,
+            map_value: false,
+            skip_key: None,
+            limits: self.limits,
+            depth: self.depth,
+            allocated,
+        }
+    }
+
+    /// Set the resource limits used while deserializing. Defaults to
+    /// [`DecodeLimits::default`].
+    #[must_use]
+    pub fn limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Only accept input in canonical bencode form.
+    ///
+    /// When enabled, integers and byte string lengths with leading zeros, an explicit `+` sign
+    /// or a negative zero are rejected with [`Error::NonCanonicalInteger`], and dictionaries
+    /// whose keys are not strictly ascending by their raw bytes are rejected with
+    /// [`Error::UnsortedKey`] or [`Error::DuplicateKey`]. Input accepted in this mode re-encodes
+    /// to exactly the same bytes.
+    #[must_use]
+    pub fn require_canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Only accept `0` and `1` as booleans. Enabled by default.
+    ///
+    /// Booleans are serialized as the integers `0` and `1`. When this is disabled, any other
+    /// integer is accepted as well, and is true if it is not zero.
+    #[must_use]
+    pub fn strict_bools(mut self, strict: bool) -> Self {
+        self.strict_bools = strict;
+        self
+    }
+
+    /// Set how floats are decoded. Defaults to [`FloatPolicy::Reject`], with which floats are
+    /// only deserialized from integers.
+    ///
+    /// Other policies decode floats from the representation the serializer produces with the
+    /// same policy, and reject anything else.
+    #[must_use]
+    pub fn float_policy(mut self, policy: FloatPolicy) -> Self {
+        self.float = policy;
+        self
+    }
+
+    /// Set how `None` and unit values are decoded outside of a dictionary. Defaults to
+    /// [`NonePolicy::Omit`], with which an `Option` always deserializes as `Some`, unless it is
+    /// a missing dictionary entry.
+    #[must_use]
+    pub fn none_policy(mut self, policy: NonePolicy) -> Self {
+        self.none = policy;
+        self
+    }
+
+    /// Set how enums are decoded. Defaults to [`EnumPolicy::ExternallyTagged`].
+    #[must_use]
+    pub fn enum_policy(mut self, policy: EnumPolicy) -> Self {
+        self.enums = policy;
+        self
+    }
+
+    /// Fails if a list or dictionary that already has `count` elements cannot have another.
+    fn check_elements(&self, count: usize) -> Result<()> {
+        if count >= self.limits.max_elements {
+            return Err(Error::LimitExceeded(format!(
+                "Limit Exceeded: more than {} elements",
+                self.limits.max_elements
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<R: io::Read> Deserializer<IoRead<R>> {
+    /// Create a new deserializer reading from a `std::io` input stream.
+    ///
+    /// Byte strings are always copied out of the stream, so the deserialized type cannot borrow
+    /// from the input. Use [`Deserializer::from_slice`] for zero-copy deserialization.
+    ///
+    /// The stream is read one byte at a time, so that nothing past the end of the deserialized
+    /// value is consumed. Each byte is then a separate `read` call, which for a `File` or
+    /// `TcpStream` is a system call. Wrap such streams in a `std::io::BufReader` and use
+    /// [`Deserializer::from_buf_reader`] instead, which reads them in large chunks and still
+    /// consumes only the bytes of the value from the `BufReader`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # fn main() -> Result<(), serde_bencode::Error> {
+    /// use serde::Deserialize;
+    /// use serde_bencode::{Deserializer, Value};
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    ///
+    /// let file = BufReader::new(File::open("file.torrent")?);
+    /// let torrent = Value::deserialize(&mut Deserializer::from_buf_reader(file))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(reader: R) -> Self {
+        Deserializer::with_read(IoRead::new(reader))
+    }
+}
+
+impl<R: io::BufRead> Deserializer<BufIoRead<R>> {
+    /// Create a new deserializer reading from a buffered `std::io` input stream, such as a
+    /// `std::io::BufReader`.
+    ///
+    /// Only the bytes of the deserialized value are consumed from the stream, so further values
+    /// can be read from it afterwards.
+    ///
+    /// # Examples
+    /// ```
+    /// # fn main() -> Result<(), serde_bencode::Error> {
+    /// use serde::Deserialize;
+    /// use serde_bencode::Deserializer;
+    /// use std::io::{BufReader, Read};
+    ///
+    /// let mut reader = BufReader::new(&b"i1ei2etail"[..]);
+    /// let a = i64::deserialize(&mut Deserializer::from_buf_reader(&mut reader))?;
+    /// let b = i64::deserialize(&mut Deserializer::from_buf_reader(&mut reader))?;
+    /// assert_eq!((a, b), (1, 2));
+    ///
+    /// let mut tail = String::new();
+    /// reader.read_to_string(&mut tail)?;
+    /// assert_eq!(tail, "tail");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_buf_reader(reader: R) -> Self {
+        Deserializer::with_read(BufIoRead::new(reader))
+    }
+}
+
+impl<'a> Deserializer<SliceRead<'a>> {
+    /// Create a new deserializer reading from a slice of bytes.
+    ///
+    /// Byte strings are borrowed from the slice, so the deserialized type may contain `&[u8]`,
+    /// `&str` and `Cow` fields that point into the input.
+    #[must_use]
+    pub fn from_slice(bytes: &'a [u8]) -> Self {
+        Deserializer::with_read(SliceRead::new(bytes))
+    }
 }
 
-#[allow(clippy::extra_unused_lifetimes)]
-impl<'de, R: Read> Deserializer<R> {
-    /// Create a new deserializer.
-    pub fn new(reader: R) -> Deserializer<R> {
-        Deserializer { reader, next: None }
+impl<'de, R: Read<'de>> Deserializer<R> {
+    /// Turn the deserializer into an iterator over the values of type `T` that follow each other
+    /// in the input, with nothing in between.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::de::Deserializer;
+    /// use serde_bencode::value::Value;
+    ///
+    /// // cspell:disable-next-line
+    /// let mut stream = Deserializer::from_slice(b"i1e3:abci3e").into_iter::<Value>();
+    ///
+    /// assert_eq!(stream.byte_offset(), 0);
+    /// assert_eq!(stream.next().unwrap().unwrap().as_int(), Some(1));
+    /// assert_eq!(stream.byte_offset(), 3);
+    /// assert_eq!(stream.next().unwrap().unwrap().as_str(), Some("abc"));
+    /// assert_eq!(stream.next().unwrap().unwrap().as_int(), Some(3));
+    /// assert!(stream.next().is_none());
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter<T: de::Deserialize<'de>>(self) -> StreamDeserializer<'de, R, T> {
+        StreamDeserializer {
+            de: self,
+            failed: false,
+            output: PhantomData,
+            lifetime: PhantomData,
+        }
+    }
+
+    fn next_byte(&mut self) -> Result<u8> {
+        self.read.next()?.ok_or(Error::EndOfStream)
     }
 
-    fn parse_int(&mut self) -> Result<i64> {
-        let mut buf = [0; 1];
-        let mut result = Vec::new();
+    /// Reads the text of an integer or byte string length, up to and including `terminator`.
+    /// Fails with `too_long` once there are more than `max_len` bytes without a terminator.
+    fn read_number(
+        &mut self,
+        mut digits: Digits,
+        terminator: u8,
+        max_len: usize,
+        too_long: impl FnOnce() -> Error,
+    ) -> Result<Digits> {
         loop {
-            if 1 != self.reader.read(&mut buf).map_err(Error::IoError)? {
-                return Err(Error::EndOfStream);
-            }
-            match buf[0] {
-                b'e' => {
-                    let len_str = String::from_utf8(result).map_err(|_| {
-                        Error::InvalidValue("Non UTF-8 integer encoding".to_string())
-                    })?;
-                    let len_int = len_str.parse().map_err(|_| {
-                        Error::InvalidValue(format!("Can't parse `{len_str}` as integer"))
-                    })?;
-                    return Ok(len_int);
-                }
-                n => result.push(n),
+            match self.next_byte()? {
+                b if b == terminator => return Ok(digits),
+                _ if digits.len() >= max_len => return Err(too_long()),
+                b => digits.push(b),
             }
         }
     }
 
+    fn parse_int(&mut self) -> Result<ParseResult<'de>> {
+        let max_alloc = self.limits.max_alloc;
+        let max_len = MAX_DIGITS.max(max_alloc - self.allocated);
+        let digits = self.read_number(Digits::new(), b'e', max_len, || alloc_error(max_alloc))?;
+        if digits.len() > MAX_DIGITS {
+            self.allocated += digits.len();
+        }
+        let int = match digits.to_i64() {
+            Some(i) => ParseResult::Int(i),
+            None => BigInt::from_ascii(digits.as_bytes())
+                .map(ParseResult::BigInt)
+                .ok_or_else(|| Error::InvalidValue(format!("Can't parse `{digits}` as integer")))?,
+        };
+        if self.canonical && !is_canonical_integer(digits.as_bytes()) {
+            return Err(Error::NonCanonicalInteger(format!(
+                "Non-canonical integer: `{digits}`"
+            )));
+        }
+        Ok(int)
+    }
+
     fn parse_bytes_len(&mut self, len_char: u8) -> Result<usize> {
-        let mut buf = [0; 1];
-        let mut len = Vec::new();
-        len.push(len_char);
-        loop {
-            if 1 != self.reader.read(&mut buf).map_err(Error::IoError)? {
-                return Err(Error::EndOfStream);
-            }
-            match buf[0] {
-                b':' => {
-                    let len_str = String::from_utf8(len).map_err(|_| {
-                        Error::InvalidValue("Non UTF-8 integer encoding".to_string())
-                    })?;
-                    let len_int = len_str.parse().map_err(|_| {
-                        Error::InvalidValue(format!("Can't parse `{len_str}` as string length"))
-                    })?;
-                    return Ok(len_int);
-                }
-                n => len.push(n),
-            }
+        let mut digits = Digits::new();
+        digits.push(len_char);
+        let digits = self.read_number(digits, b':', MAX_DIGITS, || {
+            Error::InvalidValue(format!("String length with more than {MAX_DIGITS} digits"))
+        })?;
+        let len = digits.to_usize().ok_or_else(|| {
+            Error::InvalidValue(format!("Can't parse `{digits}` as string length"))
+        })?;
+        if self.canonical && !is_canonical_integer(digits.as_bytes()) {
+            return Err(Error::NonCanonicalInteger(format!(
+                "Non-canonical string length: `{digits}`"
+            )));
         }
+        Ok(len)
     }
 
-    fn parse_bytes(&mut self, len_char: u8) -> Result<Vec<u8>> {
+    fn parse_bytes(&mut self, len_char: u8) -> Result<Cow<'de, [u8]>> {
         let len = self.parse_bytes_len(len_char)?;
-        let mut buf = Vec::new();
+        if len > self.limits.max_bytes_len {
+            return Err(Error::LimitExceeded(format!(
+                "Limit Exceeded: byte string of length {len} (max: {})",
+                self.limits.max_bytes_len
+            )));
+        }
+        if len > self.limits.max_alloc - self.allocated {
+            return Err(alloc_error(self.limits.max_alloc));
+        }
+        self.allocated += len;
+        self.read.read_bytes(len)
+    }
 
-        let len_usize = u64::try_from(len)
-            .map_err(|_| Error::InvalidLength(String::from("byte string length too large")))?;
+    fn enter(&mut self) -> Result<()> {
+        if self.depth >= self.limits.max_depth {
+            return Err(Error::LimitExceeded(format!(
+                "Limit Exceeded: nesting depth of {}",
+                self.limits.max_depth
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
 
-        let actual_len = self
-            .reader
-            .by_ref()
-            .take(len_usize)
-            .read_to_end(&mut buf)
-            .map_err(Error::IoError)?;
+    fn leave(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
 
-        if len != actual_len {
-            return Err(Error::EndOfStream);
+    /// Check that the input has been consumed completely. Call this after deserializing a value
+    /// to reject anything that follows it.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Error::TrailingData`] if input remains, or if reading from the input fails.
+    pub fn end(&mut self) -> Result<()> {
+        match self.read.peek()? {
+            Some(_) => Err(Error::TrailingData {
+                offset: self.byte_offset(),
+            }),
+            None => Ok(()),
         }
-        Ok(buf)
     }
 
-    fn parse(&mut self) -> Result<ParseResult> {
-        if let Some(t) = self.next.take() {
-            return Ok(t);
+    pub(crate) fn byte_offset(&self) -> usize {
+        self.read.byte_offset()
+    }
+
+    /// Deserializes with `f`, and attaches the byte offset at which it failed to an error in the
+    /// outermost value. Errors in nested values are located as they leave their list or
+    /// dictionary.
+    fn located<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let outermost = self.depth == 0;
+        let result = f(self);
+        if outermost {
+            result.map_err(|e| e.at_offset(self.byte_offset()))
+        } else {
+            result
         }
-        let mut buf = [0; 1];
-        if 1 != self.reader.read(&mut buf).map_err(Error::IoError)? {
-            return Err(Error::EndOfStream);
+    }
+
+    /// Deserializes an internally tagged enum. The dictionary is read once to find the variant
+    /// under the tag, and once more to deserialize the variant from the other entries.
+    fn deserialize_tagged<V: de::Visitor<'de>>(
+        &mut self,
+        tag: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        let offset = self.read.byte_offset();
+        let allocated = self.allocated;
+        self.read.begin_raw();
+        let variant = de::Deserializer::deserialize_map(&mut *self, TagVisitor(tag));
+        let raw = self.read.end_raw();
+        let variant = variant?.ok_or_else(|| Error::missing_field(tag))?;
+
+        // The byte strings were counted against the limits already.
+        match raw {
+            Cow::Borrowed(raw) => visitor.visit_enum(TaggedAccess {
+                de: self.nested(SliceRead::new(raw), allocated),
+                tag,
+                variant,
+                offset,
+            }),
+            Cow::Owned(raw) => visitor.visit_enum(TaggedAccess {
+                de: self.nested(BufIoRead::new(io::Cursor::new(raw)), allocated),
+                tag,
+                variant,
+                offset,
+            }),
+        }
+    }
+
+    /// Skips the next value and hands its exact encoding to the visitor.
+    fn deserialize_raw<V: de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        self.read.begin_raw();
+        let skipped = de::IgnoredAny::deserialize(&mut *self);
+        let raw = self.read.end_raw();
+        skipped?;
+        match raw {
+            Cow::Borrowed(raw) => visitor.visit_borrowed_bytes(raw),
+            Cow::Owned(raw) => visitor.visit_byte_buf(raw),
         }
-        match buf[0] {
-            b'i' => Ok(ParseResult::Int(self.parse_int()?)),
+    }
+
+    fn parse(&mut self) -> Result<ParseResult<'de>> {
+        self.map_value = false;
+        match self.next_byte()? {
+            b'i' => self.parse_int(),
             n @ b'0'..=b'9' => Ok(ParseResult::Bytes(self.parse_bytes(n)?)),
-            b'l' => Ok(ParseResult::List),
-            b'd' => Ok(ParseResult::Map),
-            b'e' => Ok(ParseResult::End),
+            b'l' => {
+                self.enter()?;
+                Ok(ParseResult::List)
+            }
+            b'd' => {
+                self.enter()?;
+                Ok(ParseResult::Map)
+            }
+            b'e' => {
+                self.leave();
+                Ok(ParseResult::End)
+            }
             c => Err(Error::InvalidValue(format!(
                 "Invalid character `{}`",
                 c as char
             ))),
         }
     }
+
+    /// Consumes the next value if it is the encoding of `None` and unit under the
+    /// [`NonePolicy`].
+    fn parse_none(&mut self) -> Result<bool> {
+        match self.none.encoded() {
+            Some(none) if self.read.starts_with(&none)? => {
+                de::IgnoredAny::deserialize(&mut *self)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Consumes the next token if it closes the current list or map.
+    fn peek_end(&mut self) -> Result<bool> {
+        match self.read.peek()? {
+            Some(b'e') => {
+                self.read.next()?;
+                self.leave();
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(Error::EndOfStream),
+        }
+    }
+
+    /// Parses the next value into a [`Value`]. Unlike deserializing a `Value`, which keeps the
+    /// last of the entries with the same key, this rejects a dictionary with duplicate keys.
+    fn parse_value(&mut self) -> Result<Value> {
+        match self.parse()? {
+            ParseResult::Int(i) => Ok(Value::Int(i)),
+            ParseResult::BigInt(i) => Ok(Value::BigInt(i)),
+            ParseResult::Bytes(bytes) => Ok(Value::Bytes(bytes.into_owned())),
+            ParseResult::List => {
+                let mut list = Vec::new();
+                while !self.peek_end()? {
+                    self.check_elements(list.len())?;
+                    let value = self
+                        .parse_value()
+                        .map_err(|e| e.in_element(list.len(), self.byte_offset()))?;
+                    list.push(value);
+                }
+                Ok(Value::List(list))
+            }
+            ParseResult::Map => {
+                let mut dict = BTreeMap::new();
+                while !self.peek_end()? {
+                    self.check_elements(dict.len())?;
+                    let key = match self.parse()? {
+                        ParseResult::Bytes(key) => key.into_owned(),
+                        r => return Err(r.to_unexpected_error("bytes")),
+                    };
+                    let offset = self.byte_offset();
+                    if dict.contains_key(&key) {
+                        return Err(Error::DuplicateKey(format!(
+                            "Duplicate Key: `{}`",
+                            String::from_utf8_lossy(&key)
+                        ))
+                        .in_key(&key, offset));
+                    }
+                    let value = self
+                        .parse_value()
+                        .map_err(|e| e.in_key(&key, self.byte_offset()))?;
+                    dict.insert(key, value);
+                }
+                Ok(Value::Dict(dict))
+            }
+            ParseResult::End => Err(Error::EndOfStream),
+        }
+    }
+}
+
+/// The text of an integer or byte string length. Numbers of up to [`INLINE_DIGITS`] characters,
+/// which covers every `i128` and `u128`, are kept on the stack while they are parsed.
+struct Digits {
+    buf: [u8; INLINE_DIGITS],
+    len: usize,
+    /// Holds the whole text instead of `buf` once it gets longer.
+    spill: Vec<u8>,
+}
+
+const INLINE_DIGITS: usize = 40;
+
+/// Maximum number of digits of a byte string length. Longer numbers do not fit in a `usize`
+/// anyway, so there is no point in reading more of them. Integers up to this long do not count
+/// against [`DecodeLimits::max_alloc`].
+const MAX_DIGITS: usize = 20;
+
+impl Digits {
+    fn new() -> Self {
+        Digits {
+            buf: [0; INLINE_DIGITS],
+            len: 0,
+            spill: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, b: u8) {
+        if self.len < INLINE_DIGITS {
+            self.buf[self.len] = b;
+        } else {
+            if self.spill.is_empty() {
+                self.spill.extend_from_slice(&self.buf);
+            }
+            self.spill.push(b);
+        }
+        self.len += 1;
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        if self.spill.is_empty() {
+            &self.buf[..self.len]
+        } else {
+            &self.spill
+        }
+    }
+
+    /// Splits off an optional sign, returning whether the number is negative and its digits.
+    fn sign(&self) -> (bool, &[u8]) {
+        match self.as_bytes() {
+            [b'-', digits @ ..] => (true, digits),
+            [b'+', digits @ ..] | digits => (false, digits),
+        }
+    }
+
+    /// Parses the digits, with the same syntax as `str::parse`. Negative numbers accumulate
+    /// downwards so that `i64::MIN` does not overflow.
+    fn to_i64(&self) -> Option<i64> {
+        let (negative, digits) = self.sign();
+        if digits.is_empty() {
+            return None;
+        }
+        digits.iter().try_fold(0_i64, |acc, &b| {
+            let digit = i64::from(ascii_digit(b)?);
+            let acc = acc.checked_mul(10)?;
+            if negative {
+                acc.checked_sub(digit)
+            } else {
+                acc.checked_add(digit)
+            }
+        })
+    }
+
+    fn to_usize(&self) -> Option<usize> {
+        let (negative, digits) = self.sign();
+        if negative || digits.is_empty() {
+            return None;
+        }
+        digits.iter().try_fold(0_usize, |acc, &b| {
+            acc.checked_mul(10)?
+                .checked_add(usize::from(ascii_digit(b)?))
+        })
+    }
+}
+
+impl std::fmt::Display for Digits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&String::from_utf8_lossy(self.as_bytes()))
+    }
 }
 
-impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
+fn alloc_error(max_alloc: usize) -> Error {
+    Error::LimitExceeded(format!(
+        "Limit Exceeded: more than {max_alloc} bytes of byte strings and long integers"
+    ))
+}
+
+fn ascii_digit(b: u8) -> Option<u8> {
+    b.is_ascii_digit().then(|| b - b'0')
+}
+
+/// Checks that the digits of an integer or byte string length have no leading zeros, no `+`
+/// sign and are not a negative zero.
+fn is_canonical_integer(digits: &[u8]) -> bool {
+    match digits {
+        [b'0'] => true,
+        [b'-', b'1'..=b'9', rest @ ..] | [b'1'..=b'9', rest @ ..] => {
+            rest.iter().all(u8::is_ascii_digit)
+        }
+        _ => false,
+    }
+}
+
+impl<'de, R: Read<'de>> de::Deserializer<'de> for &mut Deserializer<R> {
     type Error = Error;
 
     #[inline]
     fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        match self.parse()? {
+        self.located(|de| match de.parse()? {
             ParseResult::Int(i) => visitor.visit_i64(i),
-            ParseResult::Bytes(s) => visitor.visit_bytes(s.as_ref()),
-            ParseResult::List => visitor.visit_seq(BencodeAccess::new(self, None)),
-            ParseResult::Map => visitor.visit_map(BencodeAccess::new(self, None)),
+            ParseResult::BigInt(i) => i.visit(visitor),
+            ParseResult::Bytes(bytes) => visit_bytes_or_str(bytes, visitor),
+            ParseResult::List => visitor.visit_seq(BencodeAccess::new(de, None)),
+            ParseResult::Map => {
+                let skip_key = de.skip_key.take();
+                let mut access = BencodeAccess::new(de, None);
+                access.skip_key = skip_key;
+                visitor.visit_map(access)
+            }
             ParseResult::End => Err(Error::EndOfStream),
-        }
+        })
     }
 
     forward_to_deserialize_any! {
-        bool char i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 unit bytes byte_buf seq map unit_struct
-        tuple_struct ignored_any struct
+        char i8 i16 i32 i64 u8 u16 u32 u64 seq map tuple_struct struct
+    }
+
+    fn deserialize_i128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.read.peek()? != Some(b'i') {
+            return self.deserialize_any(visitor);
+        }
+        self.located(|de| match de.parse()? {
+            ParseResult::Int(i) => visitor.visit_i64(i),
+            ParseResult::BigInt(i) => i.visit_wide(visitor),
+            token => Err(token.to_unexpected_error("an integer")),
+        })
+    }
+
+    fn deserialize_u128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_i128(visitor)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.located(|de| match de.parse()? {
+            ParseResult::Bytes(Cow::Borrowed(s)) => visitor.visit_borrowed_bytes(s),
+            ParseResult::Bytes(Cow::Owned(s)) => visitor.visit_byte_buf(s),
+            r => Err(r.to_unexpected_error("bytes")),
+        })
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    // Skip byte strings without checking whether they are UTF-8.
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.located(|de| match de.read.peek()? {
+            Some(b'0'..=b'9') => de.deserialize_bytes(visitor),
+            _ => de.deserialize_any(visitor),
+        })
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.located(|de| match de.parse()? {
+            ParseResult::Int(0) => visitor.visit_bool(false),
+            ParseResult::Int(1) => visitor.visit_bool(true),
+            ParseResult::Int(_) | ParseResult::BigInt(_) if !de.strict_bools => {
+                visitor.visit_bool(true)
+            }
+            ParseResult::Int(i) => Err(Error::invalid_value(Unexpected::Signed(i), &"0 or 1")),
+            token => Err(token.to_unexpected_error("0 or 1")),
+        })
+    }
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.float == FloatPolicy::Reject {
+            return self.deserialize_any(visitor);
+        }
+        self.located(|de| {
+            let value = match (de.float, de.parse()?) {
+                (FloatPolicy::DecimalString, ParseResult::Bytes(bytes)) => str::from_utf8(&bytes)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| {
+                        Error::invalid_value(Unexpected::Bytes(&bytes), &"a decimal number")
+                    })?,
+                (FloatPolicy::ScaledInteger(places), ParseResult::Int(i)) => {
+                    policy::from_scaled(i, places)
+                }
+                (FloatPolicy::Ieee754, ParseResult::Bytes(bytes)) => {
+                    if let Ok(bytes) = <[u8; 4]>::try_from(&*bytes) {
+                        f64::from(f32::from_be_bytes(bytes))
+                    } else if let Ok(bytes) = <[u8; 8]>::try_from(&*bytes) {
+                        f64::from_be_bytes(bytes)
+                    } else {
+                        return Err(Error::invalid_length(bytes.len(), &"4 or 8 bytes"));
+                    }
+                }
+                (_, token) => return Err(token.to_unexpected_error("a float")),
+            };
+            visitor.visit_f64(value)
+        })
     }
 
     #[inline]
     fn deserialize_newtype_struct<V: de::Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
-        visitor.visit_newtype_struct(self)
+        if name == raw::TOKEN {
+            return self.located(|de| de.deserialize_raw(visitor));
+        }
+        self.located(|de| visitor.visit_newtype_struct(de))
     }
 
     #[inline]
     fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_some(self)
+        self.located(|de| {
+            // Within a dictionary, `None` is a missing entry.
+            if !std::mem::take(&mut de.map_value) && de.parse_none()? {
+                return visitor.visit_none();
+            }
+            visitor.visit_some(de)
+        })
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.located(|de| {
+            if de.parse_none()? {
+                return visitor.visit_unit();
+            }
+            de.deserialize_any(visitor)
+        })
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
     }
 
     #[inline]
@@ -294,7 +1295,15 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_enum(BencodeAccess::new(self, None))
+        self.located(|de| {
+            if let EnumPolicy::InternallyTagged(tag) = de.enums {
+                // Unit variants are byte strings, as with the other policies.
+                if de.read.peek()? == Some(b'd') {
+                    return de.deserialize_tagged(tag, visitor);
+                }
+            }
+            visitor.visit_enum(BencodeAccess::new(de, None))
+        })
     }
 
     // Do not delegate this to `deserialize_any` because we want to call `visit_str` instead of
@@ -304,14 +1313,27 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
     where
         V: de::Visitor<'de>,
     {
-        let bytes = self.parse().and_then(|r| match r {
-            ParseResult::Bytes(bytes) => Ok(bytes),
-            _ => Err(r.to_unexpected_error("bytes")),
-        })?;
+        self.located(|de| {
+            let bytes = de.parse().and_then(|r| match r {
+                ParseResult::Bytes(bytes) => Ok(bytes),
+                _ => Err(r.to_unexpected_error("bytes")),
+            })?;
 
-        let s = str::from_utf8(&bytes)
-            .map_err(|_| Error::invalid_value(Unexpected::Bytes(&bytes), &"utf-8 string"))?;
-        visitor.visit_str(s)
+            match bytes {
+                Cow::Borrowed(bytes) => {
+                    let s = str::from_utf8(bytes).map_err(|_| {
+                        Error::invalid_value(Unexpected::Bytes(bytes), &"utf-8 string")
+                    })?;
+                    visitor.visit_borrowed_str(s)
+                }
+                Cow::Owned(bytes) => {
+                    let s = String::from_utf8(bytes).map_err(|e| {
+                        Error::invalid_value(Unexpected::Bytes(e.as_bytes()), &"utf-8 string")
+                    })?;
+                    visitor.visit_string(s)
+                }
+            }
+        })
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -332,15 +1354,64 @@ impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
     where
         V: de::Visitor<'de>,
     {
-        self.parse().and_then(|r| match r {
-            ParseResult::List => Ok(()),
-            _ => Err(r.to_unexpected_error("list")),
-        })?;
+        self.located(|de| {
+            de.parse().and_then(|r| match r {
+                ParseResult::List => Ok(()),
+                _ => Err(r.to_unexpected_error("list")),
+            })?;
+
+            visitor.visit_seq(BencodeAccess::new(de, Some(size)))
+        })
+    }
+}
+
+/// Iterator that deserializes a stream of concatenated bencoded values into values of type `T`.
+///
+/// Created with [`Deserializer::into_iter`]. Iteration ends at the end of the input, or after the
+/// first error, which is an [`Error::Located`] carrying the byte offset at which decoding failed.
+/// The [`DecodeLimits::max_alloc`] budget applies to each value separately.
+pub struct StreamDeserializer<'de, R, T> {
+    de: Deserializer<R>,
+    failed: bool,
+    output: PhantomData<T>,
+    lifetime: PhantomData<&'de ()>,
+}
+
+impl<'de, R: Read<'de>, T: de::Deserialize<'de>> StreamDeserializer<'de, R, T> {
+    /// Create a stream deserializer for the given input source.
+    pub fn new(read: R) -> Self {
+        Deserializer::with_read(read).into_iter()
+    }
 
-        visitor.visit_seq(BencodeAccess::new(self, Some(size)))
+    /// Number of bytes consumed so far. Before a call to `next`, this is the byte offset at which
+    /// the next value starts; after it, the byte offset just past the value it returned.
+    pub fn byte_offset(&self) -> usize {
+        self.de.byte_offset()
     }
 }
 
+impl<'de, R: Read<'de>, T: de::Deserialize<'de>> Iterator for StreamDeserializer<'de, R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+        let value = match self.de.read.peek() {
+            Ok(None) => return None,
+            Ok(Some(_)) => {
+                self.de.allocated = 0;
+                T::deserialize(&mut self.de)
+            }
+            Err(e) => Err(e.at_offset(self.de.byte_offset())),
+        };
+        self.failed = value.is_err();
+        Some(value)
+    }
+}
+
+impl<R, T> std::iter::FusedIterator for StreamDeserializer<'_, R, T> where Self: Iterator {}
+
 /// Deserialize an instance of type `T` from a string of bencode.
 ///
 /// # Examples
@@ -382,6 +1453,12 @@ where
 
 /// Deserialize an instance of type `T` from a bencode byte vector.
 ///
+/// Byte strings are borrowed from `b` where `T` allows it, so fields of type `&[u8]`, `&str` or
+/// `Cow<'_, _>` are deserialized without allocating.
+///
+/// Anything following the value is ignored. Use [`from_bytes_strict`] to reject it, or
+/// [`from_bytes_partial`] to get hold of it.
+///
 /// # Examples
 /// ```
 /// # fn main() -> Result<(), serde_bencode::Error> {
@@ -411,10 +1488,180 @@ where
 ///
 /// This conversion can fail if the input bencode is improperly formatted or if the structure of
 /// the input does not match the structure expected by `T`. It can also fail if `T`'s
-/// implementation of `Deserialize` decides to fail.
+/// implementation of `Deserialize` decides to fail. The returned error is an [`Error::Located`]
+/// carrying the byte offset and key path at which decoding failed.
 pub fn from_bytes<'de, T>(b: &'de [u8]) -> Result<T>
 where
     T: de::Deserialize<'de>,
 {
-    de::Deserialize::deserialize(&mut Deserializer::new(b))
+    let mut de = Deserializer::from_slice(b);
+    de::Deserialize::deserialize(&mut de)
+}
+
+/// Deserializes a value that takes up all of `b`, with the given limits.
+pub(crate) fn from_slice_with_limits<'de, T>(b: &'de [u8], limits: DecodeLimits) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    de::Deserialize::deserialize(&mut Deserializer::from_slice(b).limits(limits))
+}
+
+/// Deserialize an instance of type `T` from a bencode byte vector, failing if anything follows
+/// the value.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::Error;
+///
+/// assert_eq!(serde_bencode::from_bytes_strict::<i64>(b"i1e").unwrap(), 1);
+/// assert!(matches!(
+///     serde_bencode::from_bytes_strict::<i64>(b"i1ei2e"),
+///     Err(Error::TrailingData { offset: 3 })
+/// ));
+/// ```
+///
+/// # Errors
+///
+/// This fails like [`from_bytes`], and with [`Error::TrailingData`] if input remains after the
+/// value.
+pub fn from_bytes_strict<'de, T>(b: &'de [u8]) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    let mut de = Deserializer::from_slice(b);
+    let value = de::Deserialize::deserialize(&mut de)?;
+    de.end()?;
+    Ok(value)
+}
+
+/// Deserialize an instance of type `T` from the start of a bencode byte vector, returning it
+/// together with the rest of the input that follows it.
+///
+/// This is useful for messages that append raw data to a bencoded header, like the `ut_metadata`
+/// messages of BEP 9.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Metadata {
+///     msg_type: u8,
+///     piece: u32,
+/// }
+///
+/// // cspell:disable-next-line
+/// let message = b"d8:msg_typei1e5:piecei0eexxxx";
+/// let (header, piece): (Metadata, _) = serde_bencode::from_bytes_partial(message)?;
+///
+/// assert_eq!((header.msg_type, header.piece), (1, 0));
+/// assert_eq!(piece, b"xxxx");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This fails like [`from_bytes`].
+pub fn from_bytes_partial<'de, T>(b: &'de [u8]) -> Result<(T, &'de [u8])>
+where
+    T: de::Deserialize<'de>,
+{
+    let mut de = Deserializer::from_slice(b);
+    let value = de::Deserialize::deserialize(&mut de)?;
+    Ok((value, &b[de.byte_offset()..]))
+}
+
+/// Check whether the bencode byte vector holds a single value in canonical form.
+///
+/// Canonical bencode has no leading zeros, `+` signs or negative zeros in integers and byte
+/// string lengths, and the keys of every dictionary are unique and sorted by their raw bytes.
+/// Use [`check_canonical`] to find where the input first departs from it, and [`canonicalize`]
+/// to fix it.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// // cspell:disable-next-line
+/// assert!(serde_bencode::is_canonical(b"d1:ai1e1:bi2ee")?);
+/// // cspell:disable-next-line
+/// assert!(!serde_bencode::is_canonical(b"d1:bi2e1:ai1ee")?);
+/// assert!(!serde_bencode::is_canonical(b"i01e")?);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This fails if the input is not valid bencode, holds anything after the value or exceeds the
+/// default [`DecodeLimits`].
+pub fn is_canonical(b: &[u8]) -> Result<bool> {
+    match check_canonical(b) {
+        Ok(()) => Ok(true),
+        Err(e)
+            if matches!(
+                e.inner(),
+                Error::NonCanonicalInteger(_) | Error::UnsortedKey(_) | Error::DuplicateKey(_)
+            ) =>
+        {
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Check that the bencode byte vector holds a single value in canonical form, as described for
+/// [`is_canonical`].
+///
+/// # Examples
+/// ```
+/// // cspell:disable-next-line
+/// let e = serde_bencode::de::check_canonical(b"d1:ai1e1:ci3e1:bi2ee").unwrap_err();
+///
+/// assert!(matches!(e.inner(), serde_bencode::Error::UnsortedKey(_)));
+/// assert_eq!(e.offset(), Some(16));
+/// ```
+///
+/// # Errors
+///
+/// This fails at the first departure from canonical form with [`Error::NonCanonicalInteger`],
+/// [`Error::UnsortedKey`] or [`Error::DuplicateKey`], whose [`Error::offset`] locates it. It also
+/// fails like [`is_canonical`].
+pub fn check_canonical(b: &[u8]) -> Result<()> {
+    let mut de = Deserializer::from_slice(b).require_canonical(true);
+    de::IgnoredAny::deserialize(&mut de)?;
+    de.end()
+}
+
+/// Re-encode the bencode byte vector in canonical form, as described for [`is_canonical`].
+///
+/// Dictionary keys are sorted, and integers and byte string lengths are written without leading
+/// zeros, `+` signs or negative zeros. The value is not decoded into any type other than
+/// [`Value`], so everything else about it is kept.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// // cspell:disable-next-line
+/// let canonical = serde_bencode::canonicalize(b"d1:bi002e1:ali-0e03:abcee")?;
+///
+/// // cspell:disable-next-line
+/// assert_eq!(canonical, b"d1:ali0e3:abce1:bi2ee");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This fails with [`Error::DuplicateKey`] if a dictionary has the same key more than once, as
+/// there is no telling which of the values to keep. It also fails if the input is not valid
+/// bencode, holds anything after the value or exceeds the default [`DecodeLimits`].
+pub fn canonicalize(b: &[u8]) -> Result<Vec<u8>> {
+    let mut de = Deserializer::from_slice(b);
+    let value = de
+        .parse_value()
+        .map_err(|e| e.at_offset(de.byte_offset()))?;
+    de.end()?;
+    crate::to_bytes(&value)
 }
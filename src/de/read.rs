@@ -0,0 +1,380 @@
+//! Input sources the deserializer can read bencode from.
+
+use crate::error::{Error, Result};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::io::{self, Read as _};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Trait used by the deserializer for iterating over input. This is manually "specialized" for
+/// iterating over `&[u8]`, so byte strings can be borrowed from the input instead of copied.
+///
+/// This trait is sealed and cannot be implemented for types outside of `serde_bencode`.
+pub trait Read<'de>: private::Sealed {
+    #[doc(hidden)]
+    fn next(&mut self) -> Result<Option<u8>>;
+
+    #[doc(hidden)]
+    fn peek(&mut self) -> Result<Option<u8>>;
+
+    /// Checks whether the remaining input starts with `prefix`, without consuming anything.
+    #[doc(hidden)]
+    fn starts_with(&mut self, prefix: &[u8]) -> Result<bool>;
+
+    /// Reads exactly `len` bytes. The result is borrowed from the input when possible.
+    #[doc(hidden)]
+    fn read_bytes(&mut self, len: usize) -> Result<Cow<'de, [u8]>>;
+
+    /// Number of bytes consumed from the input so far.
+    #[doc(hidden)]
+    fn byte_offset(&self) -> usize;
+
+    /// Starts recording the consumed input, for capturing a raw value.
+    #[doc(hidden)]
+    fn begin_raw(&mut self);
+
+    /// Stops recording and returns the input consumed since [`Read::begin_raw`].
+    #[doc(hidden)]
+    fn end_raw(&mut self) -> Cow<'de, [u8]>;
+}
+
+/// Bencode input source that reads from a `std::io` input stream.
+///
+/// The input is read one byte at a time, and nothing past the end of the value being deserialized
+/// is consumed, so further values can be read from the same stream afterwards. As every byte is a
+/// separate `read` call, wrap unbuffered streams such as files and sockets in a
+/// `std::io::BufReader` and read them with [`BufIoRead`] instead.
+#[derive(Debug)]
+pub struct IoRead<R: io::Read> {
+    reader: R,
+    /// Bytes read from the reader ahead of the current position, to look at the upcoming input.
+    lookahead: VecDeque<u8>,
+    offset: usize,
+    raw: Option<Vec<u8>>,
+}
+
+impl<R: io::Read> IoRead<R> {
+    /// Create a bencode input source to read from a `std::io` input stream.
+    pub fn new(reader: R) -> Self {
+        IoRead {
+            reader,
+            lookahead: VecDeque::new(),
+            offset: 0,
+            raw: None,
+        }
+    }
+
+    /// Consume the input source and return the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read> IoRead<R> {
+    fn consumed(&mut self, bytes: &[u8]) {
+        self.offset += bytes.len();
+        if let Some(raw) = &mut self.raw {
+            raw.extend_from_slice(bytes);
+        }
+    }
+
+    /// Reads a single byte from the reader, returning `None` at the end of the input.
+    fn read_byte(&mut self) -> Result<Option<u8>> {
+        let mut buf = [0; 1];
+        loop {
+            match self.reader.read(&mut buf) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buf[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::IoError(e)),
+            }
+        }
+    }
+}
+
+impl<R: io::Read> private::Sealed for IoRead<R> {}
+
+impl<'de, R: io::Read> Read<'de> for IoRead<R> {
+    fn next(&mut self) -> Result<Option<u8>> {
+        let b = match self.lookahead.pop_front() {
+            Some(b) => Some(b),
+            None => self.read_byte()?,
+        };
+        if let Some(b) = b {
+            self.consumed(&[b]);
+        }
+        Ok(b)
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        if self.lookahead.is_empty() {
+            if let Some(b) = self.read_byte()? {
+                self.lookahead.push_back(b);
+            }
+        }
+        Ok(self.lookahead.front().copied())
+    }
+
+    fn starts_with(&mut self, prefix: &[u8]) -> Result<bool> {
+        // Bytes are only read ahead while they match, so they are all part of the value that
+        // follows and reading them never goes past its end.
+        while self.lookahead.len() < prefix.len() {
+            if !lookahead_matches(&self.lookahead, prefix) {
+                return Ok(false);
+            }
+            match self.read_byte()? {
+                Some(b) => self.lookahead.push_back(b),
+                None => return Ok(false),
+            }
+        }
+        Ok(lookahead_matches(&self.lookahead, prefix))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<Cow<'de, [u8]>> {
+        let ahead = self.lookahead.len().min(len);
+        let mut bytes: Vec<u8> = self.lookahead.drain(..ahead).collect();
+
+        // The buffer grows as the input arrives, so a bogus length cannot make it allocate up
+        // front.
+        let remaining = u64::try_from(len - ahead)
+            .map_err(|_| Error::InvalidLength(String::from("byte string length too large")))?;
+        let result = self.reader.by_ref().take(remaining).read_to_end(&mut bytes);
+        self.consumed(&bytes);
+        result.map_err(Error::IoError)?;
+
+        if len != bytes.len() {
+            return Err(Error::EndOfStream);
+        }
+        Ok(Cow::Owned(bytes))
+    }
+
+    fn byte_offset(&self) -> usize {
+        self.offset
+    }
+
+    fn begin_raw(&mut self) {
+        self.raw = Some(Vec::new());
+    }
+
+    fn end_raw(&mut self) -> Cow<'de, [u8]> {
+        Cow::Owned(self.raw.take().unwrap_or_default())
+    }
+}
+
+/// Bencode input source that reads from a buffered `std::io` input stream.
+///
+/// Input is taken from the stream's own buffer and only the bytes of the value being deserialized
+/// are consumed from it, so further values can be read from the same stream afterwards.
+#[derive(Debug)]
+pub struct BufIoRead<R: io::BufRead> {
+    reader: R,
+    /// Bytes consumed from the reader ahead of the current position, when looking at more of the
+    /// upcoming input than its buffer holds.
+    lookahead: VecDeque<u8>,
+    offset: usize,
+    raw: Option<Vec<u8>>,
+}
+
+impl<R: io::BufRead> BufIoRead<R> {
+    /// Create a bencode input source to read from a buffered `std::io` input stream.
+    pub fn new(reader: R) -> Self {
+        BufIoRead {
+            reader,
+            lookahead: VecDeque::new(),
+            offset: 0,
+            raw: None,
+        }
+    }
+
+    /// Consume the input source and return the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::BufRead> BufIoRead<R> {
+    fn consumed(&mut self, bytes: &[u8]) {
+        self.offset += bytes.len();
+        if let Some(raw) = &mut self.raw {
+            raw.extend_from_slice(bytes);
+        }
+    }
+
+    /// Returns the buffered input of the reader, which is only empty at the end of the input.
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::IoError(e)),
+            }
+        }
+        // The input is buffered now, so asking again does not read from the stream.
+        self.reader.fill_buf().map_err(Error::IoError)
+    }
+
+    /// Consumes a single byte from the reader, returning `None` at the end of the input.
+    fn read_byte(&mut self) -> Result<Option<u8>> {
+        let b = self.fill_buf()?.first().copied();
+        if b.is_some() {
+            self.reader.consume(1);
+        }
+        Ok(b)
+    }
+}
+
+impl<R: io::BufRead> private::Sealed for BufIoRead<R> {}
+
+impl<'de, R: io::BufRead> Read<'de> for BufIoRead<R> {
+    fn next(&mut self) -> Result<Option<u8>> {
+        let b = match self.lookahead.pop_front() {
+            Some(b) => Some(b),
+            None => self.read_byte()?,
+        };
+        if let Some(b) = b {
+            self.consumed(&[b]);
+        }
+        Ok(b)
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        match self.lookahead.front() {
+            Some(&b) => Ok(Some(b)),
+            None => Ok(self.fill_buf()?.first().copied()),
+        }
+    }
+
+    fn starts_with(&mut self, prefix: &[u8]) -> Result<bool> {
+        if self.lookahead.is_empty() {
+            let buf = self.fill_buf()?;
+            if buf.len() >= prefix.len() || !prefix.starts_with(buf) {
+                return Ok(buf.starts_with(prefix));
+            }
+        }
+        // The prefix runs past the end of the reader's buffer. Bytes are only consumed ahead
+        // while they match, so they are all part of the value that follows.
+        while self.lookahead.len() < prefix.len() {
+            if !lookahead_matches(&self.lookahead, prefix) {
+                return Ok(false);
+            }
+            match self.read_byte()? {
+                Some(b) => self.lookahead.push_back(b),
+                None => return Ok(false),
+            }
+        }
+        Ok(lookahead_matches(&self.lookahead, prefix))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<Cow<'de, [u8]>> {
+        let ahead = self.lookahead.len().min(len);
+        let mut bytes: Vec<u8> = self.lookahead.drain(..ahead).collect();
+
+        // The buffer grows as the input arrives, so a bogus length cannot make it allocate up
+        // front.
+        let mut result = Ok(());
+        while bytes.len() < len {
+            let buf = match self.fill_buf() {
+                Ok([]) => break,
+                Ok(buf) => buf,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            };
+            let n = buf.len().min(len - bytes.len());
+            bytes.extend_from_slice(&buf[..n]);
+            self.reader.consume(n);
+        }
+        self.consumed(&bytes);
+        result?;
+
+        if len != bytes.len() {
+            return Err(Error::EndOfStream);
+        }
+        Ok(Cow::Owned(bytes))
+    }
+
+    fn byte_offset(&self) -> usize {
+        self.offset
+    }
+
+    fn begin_raw(&mut self) {
+        self.raw = Some(Vec::new());
+    }
+
+    fn end_raw(&mut self) -> Cow<'de, [u8]> {
+        Cow::Owned(self.raw.take().unwrap_or_default())
+    }
+}
+
+/// Checks whether the bytes read ahead agree with `prefix` as far as both go.
+fn lookahead_matches(lookahead: &VecDeque<u8>, prefix: &[u8]) -> bool {
+    lookahead.iter().zip(prefix).all(|(a, b)| a == b)
+}
+
+/// Bencode input source that reads from a slice of bytes. Byte strings are borrowed from the
+/// slice rather than copied.
+#[derive(Debug)]
+pub struct SliceRead<'a> {
+    slice: &'a [u8],
+    index: usize,
+    raw_start: usize,
+}
+
+impl<'a> SliceRead<'a> {
+    /// Create a bencode input source to read from a slice of bytes.
+    #[must_use]
+    pub fn new(slice: &'a [u8]) -> Self {
+        SliceRead {
+            slice,
+            index: 0,
+            raw_start: 0,
+        }
+    }
+}
+
+impl private::Sealed for SliceRead<'_> {}
+
+impl<'a> Read<'a> for SliceRead<'a> {
+    fn next(&mut self) -> Result<Option<u8>> {
+        let b = self.slice.get(self.index).copied();
+        if b.is_some() {
+            self.index += 1;
+        }
+        Ok(b)
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Ok(self.slice.get(self.index).copied())
+    }
+
+    fn starts_with(&mut self, prefix: &[u8]) -> Result<bool> {
+        Ok(self.slice[self.index..].starts_with(prefix))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<Cow<'a, [u8]>> {
+        if len > self.slice.len() - self.index {
+            self.index = self.slice.len();
+            return Err(Error::EndOfStream);
+        }
+        let bytes = &self.slice[self.index..self.index + len];
+        self.index += len;
+        Ok(Cow::Borrowed(bytes))
+    }
+
+    fn byte_offset(&self) -> usize {
+        self.index
+    }
+
+    fn begin_raw(&mut self) {
+        self.raw_start = self.index;
+    }
+
+    fn end_raw(&mut self) -> Cow<'a, [u8]> {
+        Cow::Borrowed(&self.slice[self.raw_start..self.index])
+    }
+}
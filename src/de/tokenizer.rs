@@ -0,0 +1,438 @@
+//! A resumable push tokenizer, for decoding bencode that arrives in pieces.
+
+use super::{alloc_error, DecodeLimits, MAX_DIGITS};
+use crate::error::{Error, Result};
+use crate::value::BigInt;
+use serde::de::DeserializeOwned;
+use std::str;
+
+/// A token of bencode, as returned by [`Tokenizer::next_token`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// An integer.
+    Int(i64),
+    /// An integer that does not fit in an `i64`.
+    BigInt(BigInt),
+    /// A byte string.
+    Bytes(&'a [u8]),
+    /// The start of a list.
+    List,
+    /// The start of a dictionary.
+    Map,
+    /// The end of a list or dictionary.
+    End,
+    /// The input fed so far ends within the next token. At least this many more bytes are needed
+    /// to complete it.
+    NeedMore(usize),
+}
+
+/// Parses the token at the start of `buf`, without consuming anything. Returns the token and the
+/// number of bytes it takes up.
+fn parse_token<'a>(buf: &'a [u8], nesting: &Nesting) -> Result<(Token<'a>, usize)> {
+    let limits = &nesting.limits;
+    let Some(&first) = buf.first() else {
+        return Ok((Token::NeedMore(1), 0));
+    };
+    match first {
+        b'i' => {
+            // Long integers count against `max_alloc`, so don't wait for more digits than that.
+            let max_len = MAX_DIGITS.max(limits.max_alloc - nesting.allocated);
+            let Some(digits) = take_digits(&buf[1..], b'e', max_len, || nesting.alloc_error())?
+            else {
+                return Ok((Token::NeedMore(1), 0));
+            };
+            let int = match digits.parse() {
+                Ok(int) => Token::Int(int),
+                Err(_) => Token::BigInt(digits.parse()?),
+            };
+            Ok((int, digits.len() + 2))
+        }
+        b'0'..=b'9' => {
+            let Some(digits) = take_digits(buf, b':', MAX_DIGITS, || {
+                Error::InvalidValue(format!("String length with more than {MAX_DIGITS} digits"))
+            })?
+            else {
+                return Ok((Token::NeedMore(1), 0));
+            };
+            let len: usize = digits.parse().map_err(|_| {
+                Error::InvalidValue(format!("Can't parse `{digits}` as string length"))
+            })?;
+            if len > limits.max_bytes_len {
+                return Err(Error::LimitExceeded(format!(
+                    "Limit Exceeded: byte string of length {len} (max: {})",
+                    limits.max_bytes_len
+                )));
+            }
+            let start = digits.len() + 1;
+            let end = start.saturating_add(len);
+            if buf.len() < end {
+                return Ok((Token::NeedMore(end - buf.len()), 0));
+            }
+            Ok((Token::Bytes(&buf[start..end]), end))
+        }
+        b'l' => Ok((Token::List, 1)),
+        b'd' => Ok((Token::Map, 1)),
+        b'e' => Ok((Token::End, 1)),
+        c => Err(Error::InvalidValue(format!(
+            "Invalid character `{}`",
+            c as char
+        ))),
+    }
+}
+
+/// Returns the text in front of `terminator`, or `None` if the terminator has not arrived yet.
+/// Fails with `too_long` once there are more than `max_len` bytes without a terminator.
+fn take_digits(
+    buf: &[u8],
+    terminator: u8,
+    max_len: usize,
+    too_long: impl FnOnce() -> Error,
+) -> Result<Option<&str>> {
+    let Some(end) = buf
+        .iter()
+        .take(max_len.saturating_add(1))
+        .position(|&b| b == terminator)
+    else {
+        if buf.len() > max_len {
+            return Err(too_long());
+        }
+        return Ok(None);
+    };
+    str::from_utf8(&buf[..end])
+        .map(Some)
+        .map_err(|_| Error::InvalidValue("Non UTF-8 integer encoding".to_string()))
+}
+
+/// Tracks the nesting of the tokens of a value, and enforces the limits on it.
+#[derive(Debug)]
+struct Nesting {
+    limits: DecodeLimits,
+    /// The lists and dictionaries that are open, innermost last.
+    containers: Vec<Container>,
+    allocated: usize,
+}
+
+/// A list or dictionary that has not been closed yet.
+#[derive(Debug)]
+struct Container {
+    map: bool,
+    /// Number of values started in it so far, counting keys as values.
+    values: usize,
+}
+
+impl Nesting {
+    fn new(limits: DecodeLimits) -> Self {
+        Nesting {
+            limits,
+            containers: Vec::new(),
+            allocated: 0,
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.containers.len()
+    }
+
+    fn alloc_error(&self) -> Error {
+        alloc_error(self.limits.max_alloc)
+    }
+
+    /// Accounts for the next complete token, which takes up `len` bytes.
+    fn push(&mut self, token: &Token<'_>, len: usize) -> Result<()> {
+        if !matches!(token, Token::End | Token::NeedMore(_)) {
+            self.start_value()?;
+        }
+        match token {
+            Token::List | Token::Map => {
+                if self.depth() >= self.limits.max_depth {
+                    return Err(Error::LimitExceeded(format!(
+                        "Limit Exceeded: nesting depth of {}",
+                        self.limits.max_depth
+                    )));
+                }
+                self.containers.push(Container {
+                    map: *token == Token::Map,
+                    values: 0,
+                });
+            }
+            Token::End => {
+                if self.containers.pop().is_none() {
+                    return Err(Error::InvalidValue("Unexpected `e`".to_string()));
+                }
+            }
+            Token::Bytes(bytes) => self.alloc(bytes.len())?,
+            // Without the `i` and `e`.
+            Token::BigInt(_) if len - 2 > MAX_DIGITS => self.alloc(len - 2)?,
+            Token::Int(_) | Token::BigInt(_) | Token::NeedMore(_) => {}
+        }
+        if self.depth() == 0 {
+            // The value is complete, and the limits start afresh for the next one.
+            self.allocated = 0;
+        }
+        Ok(())
+    }
+
+    /// Counts a value starting in the innermost list or dictionary against its elements.
+    fn start_value(&mut self) -> Result<()> {
+        let max_elements = self.limits.max_elements;
+        let Some(container) = self.containers.last_mut() else {
+            return Ok(());
+        };
+        // Each entry of a dictionary is a key and a value, and counts once, at its key.
+        let (elements, is_value) = if container.map {
+            (container.values / 2, container.values % 2 == 1)
+        } else {
+            (container.values, false)
+        };
+        if !is_value && elements >= max_elements {
+            return Err(Error::LimitExceeded(format!(
+                "Limit Exceeded: more than {max_elements} elements"
+            )));
+        }
+        container.values += 1;
+        Ok(())
+    }
+
+    fn alloc(&mut self, len: usize) -> Result<()> {
+        if len > self.limits.max_alloc - self.allocated {
+            return Err(self.alloc_error());
+        }
+        self.allocated += len;
+        Ok(())
+    }
+}
+
+/// Finds where the first bencoded value in a growing buffer ends.
+///
+/// Tokens are only scanned once: each call resumes after the last complete token seen.
+#[derive(Debug)]
+pub(crate) struct ValueScanner {
+    nesting: Nesting,
+    /// Offset of the next token in the buffer.
+    offset: usize,
+}
+
+impl ValueScanner {
+    pub(crate) fn new(limits: DecodeLimits) -> Self {
+        ValueScanner {
+            nesting: Nesting::new(limits),
+            offset: 0,
+        }
+    }
+
+    #[cfg(any(feature = "tokio", feature = "futures"))]
+    pub(crate) fn limits(&self) -> DecodeLimits {
+        self.nesting.limits
+    }
+
+    /// Scans the tokens in `buf` that have not been seen yet. `buf` must start with the bytes
+    /// passed to the previous calls.
+    ///
+    /// Once the first value is complete, returns its length and resets the scanner, so it can
+    /// look for the next value in a buffer that starts after this one.
+    pub(crate) fn scan(&mut self, buf: &[u8]) -> Result<Option<usize>> {
+        self.scan_tokens(buf).map_err(|e| e.at_offset(self.offset))
+    }
+
+    fn scan_tokens(&mut self, buf: &[u8]) -> Result<Option<usize>> {
+        loop {
+            let (token, len) = parse_token(&buf[self.offset..], &self.nesting)?;
+            if let Token::NeedMore(_) = token {
+                return Ok(None);
+            }
+            self.nesting.push(&token, len)?;
+            self.offset += len;
+
+            if self.nesting.depth() == 0 {
+                let end = self.offset;
+                self.offset = 0;
+                return Ok(Some(end));
+            }
+        }
+    }
+}
+
+/// Collects the bytes of a single bencoded value from chunks of input.
+#[cfg(any(feature = "tokio", feature = "futures"))]
+#[derive(Debug)]
+pub(crate) struct ValueBuffer {
+    buf: Vec<u8>,
+    scanner: ValueScanner,
+}
+
+#[cfg(any(feature = "tokio", feature = "futures"))]
+impl ValueBuffer {
+    pub(crate) fn new(limits: DecodeLimits) -> Self {
+        ValueBuffer {
+            buf: Vec::new(),
+            scanner: ValueScanner::new(limits),
+        }
+    }
+
+    /// Appends the part of `chunk` that belongs to the value. Returns the number of bytes taken
+    /// from `chunk`, and whether the value is complete.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Result<(usize, bool)> {
+        if chunk.is_empty() {
+            return Err(Error::EndOfStream.at_offset(self.buf.len()));
+        }
+        let start = self.buf.len();
+        self.buf.extend_from_slice(chunk);
+        match self.scanner.scan(&self.buf)? {
+            Some(end) => {
+                self.buf.truncate(end);
+                Ok((end - start, true))
+            }
+            None => Ok((chunk.len(), false)),
+        }
+    }
+
+    /// Deserializes the complete value.
+    pub(crate) fn deserialize<T: DeserializeOwned>(&self) -> Result<T> {
+        super::from_slice_with_limits(&self.buf, self.scanner.limits())
+    }
+}
+
+/// A push tokenizer for bencode that arrives in pieces, for example from a non-blocking socket.
+///
+/// Input is handed over with [`Tokenizer::feed`] as it arrives. [`Tokenizer::next_token`] then
+/// returns the tokens of the input one at a time, and reports [`Token::NeedMore`] instead of
+/// failing when the input fed so far ends within a token. Feeding more input resumes exactly where
+/// it stopped. Alternatively, [`Tokenizer::next_value`] deserializes a whole value once all of it
+/// has arrived.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::de::{Token, Tokenizer};
+///
+/// let mut tokenizer = Tokenizer::new();
+/// tokenizer.feed(b"li42e4:sp");
+///
+/// assert_eq!(tokenizer.next_token().unwrap(), Token::List);
+/// assert_eq!(tokenizer.next_token().unwrap(), Token::Int(42));
+/// assert_eq!(tokenizer.next_token().unwrap(), Token::NeedMore(2));
+///
+/// tokenizer.feed(b"ame");
+/// assert_eq!(tokenizer.next_token().unwrap(), Token::Bytes(b"spam"));
+/// assert_eq!(tokenizer.next_token().unwrap(), Token::End);
+/// ```
+#[derive(Debug)]
+pub struct Tokenizer {
+    buf: Vec<u8>,
+    /// Start of the input not consumed yet.
+    pos: usize,
+    /// Number of consumed bytes dropped from the start of `buf`.
+    dropped: usize,
+    nesting: Nesting,
+    scanner: ValueScanner,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tokenizer {
+    /// Create a tokenizer with the default [`DecodeLimits`].
+    #[must_use]
+    pub fn new() -> Self {
+        Tokenizer {
+            buf: Vec::new(),
+            pos: 0,
+            dropped: 0,
+            nesting: Nesting::new(DecodeLimits::default()),
+            scanner: ValueScanner::new(DecodeLimits::default()),
+        }
+    }
+
+    /// Set the resource limits applied to each value. Defaults to [`DecodeLimits::default`].
+    #[must_use]
+    pub fn limits(mut self, limits: DecodeLimits) -> Self {
+        self.nesting = Nesting::new(limits);
+        self.scanner = ValueScanner::new(limits);
+        self
+    }
+
+    /// Hand over the next chunk of input.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.dropped += self.pos;
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Number of bytes consumed so far.
+    #[must_use]
+    pub fn byte_offset(&self) -> usize {
+        self.dropped + self.pos
+    }
+
+    /// The input that has been fed but not consumed yet.
+    #[must_use]
+    pub fn remaining(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// Consume the next token, or report how many more bytes are needed to complete it.
+    ///
+    /// # Errors
+    ///
+    /// This fails if the input is not valid bencode or exceeds the limits. The error is an
+    /// [`Error::Located`] carrying the byte offset of the offending token, which is not consumed.
+    pub fn next_token(&mut self) -> Result<Token<'_>> {
+        let offset = self.byte_offset();
+        let (token, len) =
+            parse_token(&self.buf[self.pos..], &self.nesting).map_err(|e| e.at_offset(offset))?;
+        self.nesting
+            .push(&token, len)
+            .map_err(|e| e.at_offset(offset))?;
+        self.pos += len;
+        // Any value partially scanned by `next_value` has just been broken up.
+        self.scanner = ValueScanner::new(self.nesting.limits);
+        Ok(token)
+    }
+
+    /// Deserialize the next value once all of it has been fed, consuming it. Returns `None` if
+    /// more input is needed first.
+    ///
+    /// # Examples
+    /// ```
+    /// use serde_bencode::de::Tokenizer;
+    ///
+    /// let mut tokenizer = Tokenizer::new();
+    /// tokenizer.feed(b"li1ei");
+    /// assert_eq!(tokenizer.next_value::<Vec<i64>>().unwrap(), None);
+    ///
+    /// tokenizer.feed(b"2eei3e");
+    /// assert_eq!(tokenizer.next_value::<Vec<i64>>().unwrap(), Some(vec![1, 2]));
+    /// assert_eq!(tokenizer.next_value::<i64>().unwrap(), Some(3));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This fails if called in the middle of a value whose first tokens were consumed with
+    /// [`Tokenizer::next_token`], if the input is not valid bencode or exceeds the limits, or for
+    /// the same reasons as [`crate::from_bytes`].
+    pub fn next_value<T: DeserializeOwned>(&mut self) -> Result<Option<T>> {
+        let offset = self.byte_offset();
+        if self.nesting.depth() != 0 {
+            return Err(
+                Error::Custom("`next_value` called in the middle of a value".to_string())
+                    .at_offset(offset),
+            );
+        }
+        let Some(len) = self
+            .scanner
+            .scan(&self.buf[self.pos..])
+            .map_err(|e| e.offset_by(offset))?
+        else {
+            return Ok(None);
+        };
+        let value =
+            super::from_slice_with_limits(&self.buf[self.pos..self.pos + len], self.nesting.limits);
+        self.pos += len;
+        value.map(Some).map_err(|e| e.offset_by(offset))
+    }
+}
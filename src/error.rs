@@ -43,11 +43,145 @@ pub enum Error {
     /// Raised when deserializing a struct, but there is more than one field with the same name.
     DuplicateField(String),
 
+    /// Raised in canonical mode when an integer or byte string length is not in its canonical
+    /// form, for example when it has leading zeros, an explicit `+` sign or is negative zero.
+    NonCanonicalInteger(String),
+
+    /// Raised in canonical mode when the keys of a dictionary are not sorted by their raw bytes.
+    UnsortedKey(String),
+
+    /// Raised in canonical mode when a dictionary contains the same key more than once, and when
+    /// serializing a dictionary with the same key more than once.
+    DuplicateKey(String),
+
+    /// Raised when the input exceeds one of the configured [`DecodeLimits`].
+    ///
+    /// [`DecodeLimits`]: crate::de::DecodeLimits
+    LimitExceeded(String),
+
     /// Catchall for any other kind of error.
     Custom(String),
 
     /// Unexpected end of input stream.
     EndOfStream,
+
+    /// Raised when input remains after the value that was deserialized, where none was expected.
+    TrailingData {
+        /// The byte offset in the input at which the trailing data starts.
+        offset: usize,
+    },
+
+    /// Raised when deserializing fails, wrapping the underlying error with the position in the
+    /// input at which it was detected.
+    Located {
+        /// The underlying error.
+        error: Box<Error>,
+        /// The byte offset in the input at which the error was detected.
+        offset: usize,
+        /// The dictionary keys and list indices leading to the value that failed to
+        /// deserialize, for example `info.files[3].length`. Empty for the top-level value.
+        ///
+        /// Keys that are empty or contain `.`, `[` or `]` are written as a quoted string in
+        /// brackets, for example `info["a.b"]`.
+        path: String,
+    },
+}
+
+impl Error {
+    /// Returns the byte offset in the input at which deserializing failed, if known.
+    #[must_use]
+    pub fn offset(&self) -> Option<usize> {
+        match *self {
+            Error::Located { offset, .. } => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the path of dictionary keys and list indices leading to the value that failed to
+    /// deserialize, for example `info.files[3].length`, if known.
+    #[must_use]
+    pub fn path(&self) -> Option<&str> {
+        match *self {
+            Error::Located { ref path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error without its location.
+    #[must_use]
+    pub fn inner(&self) -> &Error {
+        match *self {
+            Error::Located { ref error, .. } => error,
+            _ => self,
+        }
+    }
+
+    pub(crate) fn at_offset(self, offset: usize) -> Error {
+        match self {
+            located @ Error::Located { .. } => located,
+            error => Error::Located {
+                error: Box::new(error),
+                offset,
+                path: String::new(),
+            },
+        }
+    }
+
+    /// Shifts the offset of a located error by `base`, for input that started at that offset.
+    pub(crate) fn offset_by(self, base: usize) -> Error {
+        match self {
+            Error::Located {
+                error,
+                offset,
+                path,
+            } => Error::Located {
+                error,
+                offset: base + offset,
+                path,
+            },
+            error => error,
+        }
+    }
+
+    pub(crate) fn in_key(self, key: &[u8], offset: usize) -> Error {
+        let key = String::from_utf8_lossy(key);
+        if key.is_empty() || key.contains(['.', '[', ']']) {
+            self.prepend_path(&format!("[{key:?}]"), offset)
+        } else {
+            self.prepend_path(&key, offset)
+        }
+    }
+
+    pub(crate) fn in_element(self, index: usize, offset: usize) -> Error {
+        self.prepend_path(&format!("[{index}]"), offset)
+    }
+
+    fn prepend_path(self, segment: &str, offset: usize) -> Error {
+        let (error, offset, path) = match self {
+            Error::Located {
+                error,
+                offset,
+                path,
+            } => (error, offset, path),
+            error => (Box::new(error), offset, String::new()),
+        };
+        let path = if path.is_empty() || path.starts_with('[') {
+            format!("{segment}{path}")
+        } else {
+            format!("{segment}.{path}")
+        };
+        Error::Located {
+            error,
+            offset,
+            path,
+        }
+    }
+}
+
+impl From<IoError> for Error {
+    fn from(error: IoError) -> Self {
+        Error::IoError(error)
+    }
 }
 
 impl SerError for Error {
@@ -98,6 +232,7 @@ impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match *self {
             Error::IoError(ref error) => Some(error),
+            Error::Located { ref error, .. } => Some(&**error),
             _ => None,
         }
     }
@@ -107,6 +242,17 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let message = match *self {
             Error::IoError(ref error) => return error.fmt(f),
+            Error::Located {
+                ref error,
+                offset,
+                ref path,
+            } => {
+                return if path.is_empty() {
+                    write!(f, "{error} at byte offset {offset}")
+                } else {
+                    write!(f, "{error} at byte offset {offset} (path: `{path}`)")
+                }
+            }
             Error::InvalidType(ref s)
             | Error::InvalidValue(ref s)
             | Error::InvalidLength(ref s)
@@ -114,8 +260,15 @@ impl fmt::Display for Error {
             | Error::UnknownField(ref s)
             | Error::MissingField(ref s)
             | Error::DuplicateField(ref s)
+            | Error::NonCanonicalInteger(ref s)
+            | Error::UnsortedKey(ref s)
+            | Error::DuplicateKey(ref s)
+            | Error::LimitExceeded(ref s)
             | Error::Custom(ref s) => s,
             Error::EndOfStream => "End of stream",
+            Error::TrailingData { offset } => {
+                return write!(f, "Trailing data at byte offset {offset}")
+            }
         };
         f.write_str(message)
     }
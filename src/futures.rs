@@ -0,0 +1,82 @@
+//! Decode and encode bencode asynchronously with the [`futures`](https://docs.rs/futures) I/O
+//! traits.
+//!
+//! Requires the `futures` feature.
+
+use crate::de::tokenizer::ValueBuffer;
+use crate::de::DecodeLimits;
+use crate::error::{Error, Result};
+use futures_util::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+
+/// Deserialize an instance of type `T` from an asynchronous input stream.
+///
+/// The input is tokenized as it arrives, and handed to serde once a complete value has been read.
+/// Exactly the bytes of that value are consumed from `reader`, so whatever follows can be read
+/// from it afterwards. Wrap a plain `AsyncRead` in a `BufReader` to use it here.
+///
+/// # Examples
+/// ```
+/// # futures_executor::block_on(async {
+/// // cspell:disable-next-line
+/// let mut input = &b"d1:y1:qei1e"[..];
+///
+/// let message: serde_bencode::value::Value =
+///     serde_bencode::futures::from_async_reader(&mut input).await?;
+///
+/// assert_eq!(message["y"].as_str(), Some("q"));
+/// assert_eq!(input, b"i1e");
+/// # Ok::<(), serde_bencode::Error>(())
+/// # }).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// This fails if reading from `reader` fails, if the input ends before the value is complete, or
+/// for the same reasons as [`crate::from_bytes`].
+pub async fn from_async_reader<R, T>(reader: R) -> Result<T>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+{
+    from_async_reader_with_limits(reader, DecodeLimits::default()).await
+}
+
+/// Deserialize an instance of type `T` from an asynchronous input stream, like
+/// [`from_async_reader`], with the given limits instead of [`DecodeLimits::default`].
+///
+/// # Errors
+///
+/// This fails like [`from_async_reader`], and with [`Error::LimitExceeded`] if the value exceeds
+/// the limits.
+pub async fn from_async_reader_with_limits<R, T>(mut reader: R, limits: DecodeLimits) -> Result<T>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut value = ValueBuffer::new(limits);
+    loop {
+        let chunk = reader.fill_buf().await?;
+        let (taken, complete) = value.push(chunk)?;
+        reader.consume_unpin(taken);
+        if complete {
+            return value.deserialize();
+        }
+    }
+}
+
+/// Serialize the given data as bencode into an asynchronous output stream.
+///
+/// # Errors
+///
+/// This fails for the same reasons as [`crate::to_writer`].
+pub async fn to_async_writer<W, T>(mut writer: W, value: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: ?Sized + Serialize,
+{
+    let mut bytes = Vec::new();
+    crate::to_writer(&mut bytes, value)?;
+    writer.write_all(&bytes).await.map_err(Error::IoError)
+}
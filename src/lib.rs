@@ -36,12 +36,44 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! # Flattened fields and internally tagged enums
+//!
+//! Serde decodes `#[serde(flatten)]` fields and `#[serde(tag = "...")]` enums by buffering the
+//! dictionary through its own generic representation, without asking for the types of the
+//! fields. [`Deserializer`] hands byte strings that are valid UTF-8 to it as strings, so string,
+//! `char`, enum and byte fields all work there, but a boolean field does not: bencode encodes it
+//! as an integer, which serde then refuses to read as a boolean. Declare such a field as an
+//! integer, or use [`EnumPolicy::InternallyTagged`] instead of `#[serde(tag = "...")]`.
+//!
+//! For the same reason, [`serde::Deserializer::deserialize_any`] visits byte strings that are
+//! valid UTF-8 with `visit_str` or `visit_borrowed_str`, and other byte strings with
+//! `visit_bytes` or `visit_borrowed_bytes`. A visitor that only implements the latter should be
+//! driven with `deserialize_bytes` instead. Integers beyond the range of `i64` and `u64`, which
+//! serde cannot buffer as `i128` or `u128`, are visited as the newtype struct that
+//! [`value::BigInt`] deserializes from; `deserialize_i128` and `deserialize_u128` still visit
+//! them as `i128` or `u128` where they fit.
+
+#[macro_use]
+mod macros;
 
 pub mod de;
 pub mod error;
+#[cfg(feature = "futures")]
+pub mod futures;
+mod policy;
+pub mod raw;
 pub mod ser;
+#[cfg(feature = "tokio")]
+pub mod tokio;
 pub mod value;
 
-pub use de::{from_bytes, from_str, Deserializer};
+pub use de::{
+    canonicalize, from_bytes, from_bytes_partial, from_bytes_strict, from_str, is_canonical,
+    DecodeLimits, Deserializer, StreamDeserializer,
+};
 pub use error::{Error, Result};
-pub use ser::{to_bytes, to_string, Serializer};
+pub use policy::{EnumPolicy, FloatPolicy, NonePolicy};
+pub use raw::RawBencode;
+pub use ser::{to_bytes, to_string, to_writer, Serializer};
+pub use value::{from_value, to_value, Extra, Value};
@@ -0,0 +1,92 @@
+/// Construct a [`Value`](crate::value::Value) from a literal, much like `serde_json::json!`.
+///
+/// Dictionaries are written `{ key: value, ... }` and lists `[value, ...]`, and they nest. Any
+/// expression whose type implements `Into<Value>` can be used as a value. Keys are a single
+/// token, such as a string or byte string literal, a variable or a parenthesized expression,
+/// whose type implements `AsRef<[u8]>`.
+///
+/// # Examples
+/// ```
+/// use serde_bencode::bencode;
+///
+/// let id = b"abcdefghij0123456789";
+/// let port: u16 = 6881;
+/// let message = bencode!({
+///     "t": "aa",
+///     "y": "q",
+///     "q": "announce_peer",
+///     "a": {
+///         "id": id,
+///         "port": port,
+///         "implied_port": 1,
+///         "tags": ["a", b"\xff", -1 + 2, []],
+///     },
+/// });
+///
+/// assert_eq!(message["a"]["port"].as_int(), Some(6881));
+/// assert_eq!(message["a"]["tags"][2].as_int(), Some(1));
+/// ```
+#[macro_export]
+macro_rules! bencode {
+    ($($value:tt)+) => {
+        $crate::bencode_internal!(@value $($value)+)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! bencode_internal {
+    // A single value.
+    (@value [ $($elems:tt)* ]) => {
+        $crate::value::Value::List($crate::bencode_internal!(@list [] () $($elems)*))
+    };
+    (@value { $($entries:tt)* }) => {
+        $crate::value::Value::Dict({
+            #[allow(unused_mut)]
+            let mut dict = ::std::collections::BTreeMap::new();
+            $crate::bencode_internal!(@dict dict $($entries)*);
+            dict
+        })
+    };
+    (@value $value:expr) => {
+        ::std::convert::Into::<$crate::value::Value>::into($value)
+    };
+
+    // List elements, collected one token at a time up to the next comma.
+    (@list [$($done:expr,)*] ()) => {
+        ::std::vec![$($done),*]
+    };
+    (@list [$($done:expr,)*] ($($cur:tt)+)) => {
+        ::std::vec![$($done,)* $crate::bencode_internal!(@value $($cur)+)]
+    };
+    (@list [$($done:expr,)*] ($($cur:tt)+) , $($rest:tt)*) => {
+        $crate::bencode_internal!(
+            @list [$($done,)* $crate::bencode_internal!(@value $($cur)+),] () $($rest)*
+        )
+    };
+    (@list [$($done:expr,)*] ($($cur:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::bencode_internal!(@list [$($done,)*] ($($cur)* $next) $($rest)*)
+    };
+
+    // Dictionary entries, each value collected one token at a time up to the next comma.
+    (@dict $dict:ident) => {};
+    (@dict $dict:ident $key:tt : $($rest:tt)*) => {
+        $crate::bencode_internal!(@entry $dict $key () $($rest)*)
+    };
+    (@entry $dict:ident $key:tt ($($cur:tt)+)) => {
+        $crate::bencode_internal!(@insert $dict $key ($($cur)+));
+    };
+    (@entry $dict:ident $key:tt ($($cur:tt)+) , $($rest:tt)*) => {
+        $crate::bencode_internal!(@insert $dict $key ($($cur)+));
+        $crate::bencode_internal!(@dict $dict $($rest)*);
+    };
+    (@entry $dict:ident $key:tt ($($cur:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::bencode_internal!(@entry $dict $key ($($cur)* $next) $($rest)*)
+    };
+    (@insert $dict:ident $key:tt ($($value:tt)+)) => {
+        $dict.insert(
+            ::std::convert::AsRef::<[u8]>::as_ref(&$key).to_vec(),
+            $crate::bencode_internal!(@value $($value)+),
+        )
+    };
+}
@@ -0,0 +1,182 @@
+//! Encodings for the values bencode has no type for.
+
+use crate::error::{Error, Result};
+
+/// How [`crate::Serializer`] encodes `f32` and `f64`, and how [`crate::Deserializer`] decodes
+/// them.
+///
+/// Bencode has no floating point type, so there is no standard encoding and serializing a float
+/// fails by default. The other policies are conventions that both ends have to agree on.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde::{Deserialize, Serialize};
+/// use serde_bencode::{Deserializer, FloatPolicy, Serializer};
+///
+/// let mut ser = Serializer::new().float_policy(FloatPolicy::DecimalString);
+/// 0.25_f64.serialize(&mut ser)?;
+/// assert_eq!(ser.as_ref(), b"4:0.25");
+///
+/// let mut de = Deserializer::from_slice(b"4:0.25").float_policy(FloatPolicy::DecimalString);
+/// assert_eq!(f64::deserialize(&mut de)?, 0.25);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatPolicy {
+    /// Serializing a float fails. Floats can still be deserialized from integers.
+    #[default]
+    Reject,
+
+    /// A byte string holding the shortest decimal representation that reads back as the same
+    /// value, e.g. `4:0.25`.
+    DecimalString,
+
+    /// An integer holding the value multiplied by ten to the power of the given number of
+    /// decimal places, rounded to the nearest integer, e.g. `i250e` for `0.25` with 3 decimal
+    /// places. Floats that do not fit in an `i64` once scaled cannot be serialized.
+    ScaledInteger(u8),
+
+    /// A byte string holding the IEEE 754 big-endian bytes of the float: 4 bytes for an `f32`
+    /// and 8 bytes for an `f64`.
+    Ieee754,
+}
+
+/// How [`crate::Serializer`] encodes `None` and unit values outside of a dictionary, and how
+/// [`crate::Deserializer`] decodes them.
+///
+/// Within a dictionary, a `None` or unit value always leaves out the whole entry, and a missing
+/// entry deserializes as `None`. Elsewhere, such as in a list or a tuple, there is no entry to
+/// leave out. By default `None` and unit are then encoded as nothing at all, so
+/// `vec![Some(1), None]` encodes as `li1ee` and reads back as `vec![Some(1)]`. The other policies
+/// keep them, or refuse to lose them.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde::{Deserialize, Serialize};
+/// use serde_bencode::{Deserializer, NonePolicy, Serializer};
+///
+/// let mut ser = Serializer::new().none_policy(NonePolicy::EmptyList);
+/// vec![Some(1), None].serialize(&mut ser)?;
+/// assert_eq!(ser.as_ref(), b"li1elee");
+///
+/// let mut de = Deserializer::from_slice(b"li1elee").none_policy(NonePolicy::EmptyList);
+/// assert_eq!(Vec::<Option<i64>>::deserialize(&mut de)?, vec![Some(1), None]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonePolicy {
+    /// `None` and unit are encoded as nothing at all, and any value deserializes as `Some`.
+    #[default]
+    Omit,
+
+    /// `None` and unit are encoded as an empty list, `le`. `Some` of an empty list, or of
+    /// anything else that encodes as `le`, then reads back as `None`.
+    EmptyList,
+
+    /// `None` and unit are encoded as the given byte string. `Some` of a value that encodes as
+    /// the same byte string then reads back as `None`.
+    Sentinel(&'static [u8]),
+
+    /// Serializing `None` or unit outside of a dictionary fails.
+    Reject,
+}
+
+/// How [`crate::Serializer`] encodes enums, and how [`crate::Deserializer`] decodes them.
+///
+/// Serde's `#[serde(tag = "...")]` attribute buffers the enum through serde's own generic
+/// representation, which cannot tell that a bencode integer is a boolean, so a variant with a
+/// boolean field does not decode (see the
+/// [crate documentation](crate#flattened-fields-and-internally-tagged-enums)). Setting
+/// [`EnumPolicy::InternallyTagged`] instead lets the deserializer find the tag in the dictionary
+/// itself, and then decode the variant from it.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde::{Deserialize, Serialize};
+/// use serde_bencode::{Deserializer, EnumPolicy, Serializer};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// enum Message {
+///     #[serde(rename = "q")]
+///     Query { q: String, t: String },
+///     #[serde(rename = "e")]
+///     Error { e: (i64, String), t: String },
+/// }
+///
+/// let message = Message::Query {
+///     q: "ping".to_string(),
+///     t: "aa".to_string(),
+/// };
+/// let mut ser = Serializer::new().enum_policy(EnumPolicy::InternallyTagged("y"));
+/// message.serialize(&mut ser)?;
+/// assert_eq!(ser.as_ref(), b"d1:q4:ping1:t2:aa1:y1:qe");
+///
+/// let mut de = Deserializer::from_slice(ser.as_ref())
+///     .enum_policy(EnumPolicy::InternallyTagged("y"));
+/// assert_eq!(Message::deserialize(&mut de)?, message);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnumPolicy {
+    /// A unit variant is a byte string holding its name, and any other variant is a dictionary
+    /// with a single entry mapping its name to its contents, e.g. `d1:Ai1ee` for `A(1)`.
+    #[default]
+    ExternallyTagged,
+
+    /// A struct variant is a dictionary of its fields, with an extra entry under the given key
+    /// holding the variant name. A newtype variant is its contents, which must be a dictionary,
+    /// with the same extra entry. A unit variant is a byte string holding its name, so plain
+    /// enums in the fields still encode as strings. Tuple variants cannot be encoded.
+    ///
+    /// The dictionary of a tagged variant is read twice: once to find the tag, and once to decode
+    /// the contents with the same settings, so the policies apply within the contents too.
+    InternallyTagged(&'static str),
+
+    /// Like [`EnumPolicy::ExternallyTagged`], but with the index of the variant in place of its
+    /// name: an integer for a unit variant, e.g. `i0e`, and its decimal digits as the key of the
+    /// dictionary entry for any other variant, e.g. `d1:0i1ee`.
+    VariantIndex,
+}
+
+impl NonePolicy {
+    /// The encoding of `None`, if it has one.
+    pub(crate) fn encoded(self) -> Option<Vec<u8>> {
+        match self {
+            NonePolicy::EmptyList => Some(b"le".to_vec()),
+            NonePolicy::Sentinel(sentinel) => {
+                let mut encoded = format!("{}:", sentinel.len()).into_bytes();
+                encoded.extend_from_slice(sentinel);
+                Some(encoded)
+            }
+            NonePolicy::Omit | NonePolicy::Reject => None,
+        }
+    }
+}
+
+/// Scales a float to an integer with the given number of decimal places.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn to_scaled(value: f64, places: u8) -> Result<i64> {
+    // `i64::MAX` is not representable, so its nearest float is the first one out of range.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+    let scaled = (value * 10_f64.powi(i32::from(places))).round();
+    if !(-LIMIT..LIMIT).contains(&scaled) {
+        return Err(Error::InvalidValue(format!(
+            "Float `{value}` does not fit in an integer with {places} decimal places"
+        )));
+    }
+    Ok(scaled as i64)
+}
+
+/// Reverses [`to_scaled`].
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn from_scaled(value: i64, places: u8) -> f64 {
+    value as f64 / 10_f64.powi(i32::from(places))
+}
@@ -0,0 +1,193 @@
+//! A type for capturing the exact bencoded bytes of a value.
+
+use crate::de::Deserializer;
+use crate::error::Result;
+use crate::value::Value;
+use serde::de::{self, Deserialize as _};
+use serde::ser;
+use serde_bytes::Bytes;
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Name of the newtype struct through which [`RawBencode`] is passed to this crate's serializer
+/// and deserializer, so they can recognise it.
+pub(crate) const TOKEN: &str = "$serde_bencode::private::RawBencode";
+
+/// The exact bencoded bytes of a value, left undecoded.
+///
+/// When deserialized, it holds the untouched span of the input that encoded the value, which is
+/// useful for hashing, e.g. computing the info-hash of a torrent. When serialized, the bytes are
+/// written out verbatim.
+///
+/// `B` is the storage for the bytes: `Vec<u8>` (the default) copies them, `&[u8]` borrows them
+/// from the input, and `Cow<[u8]>` borrows them when possible.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::raw::RawBencode;
+/// use serde_derive::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Torrent<'a> {
+///     #[serde(borrow)]
+///     info: RawBencode<&'a [u8]>,
+/// }
+///
+/// // Non-canonical keys are kept as they are.
+/// // cspell:disable-next-line
+/// let encoded = b"d4:infod1:bi1e1:ai2eee";
+/// let torrent: Torrent<'_> = serde_bencode::from_bytes(encoded)?;
+///
+/// // cspell:disable-next-line
+/// assert_eq!(torrent.info.as_bytes(), b"d1:bi1e1:ai2ee");
+/// assert_eq!(serde_bencode::to_bytes(&torrent)?, encoded);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct RawBencode<B = Vec<u8>> {
+    bytes: B,
+}
+
+impl<B: AsRef<[u8]>> RawBencode<B> {
+    /// Wrap bytes holding exactly one bencoded value.
+    ///
+    /// # Errors
+    ///
+    /// This fails if `bytes` is not a single, well-formed bencoded value.
+    pub fn new(bytes: B) -> Result<Self> {
+        let mut de = Deserializer::from_slice(bytes.as_ref());
+        de::IgnoredAny::deserialize(&mut de)?;
+        de.end()?;
+        Ok(RawBencode { bytes })
+    }
+
+    /// The bencoded bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+
+    /// Consume the raw value and return its storage.
+    pub fn into_inner(self) -> B {
+        self.bytes
+    }
+}
+
+impl<B: AsRef<[u8]>> AsRef<[u8]> for RawBencode<B> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<B: AsRef<[u8]>> fmt::Debug for RawBencode<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawBencode")
+            .field(&String::from_utf8_lossy(self.as_bytes()))
+            .finish()
+    }
+}
+
+impl<B: AsRef<[u8]>> ser::Serialize for RawBencode<B> {
+    fn serialize<S: ser::Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        s.serialize_newtype_struct(TOKEN, Bytes::new(self.as_bytes()))
+    }
+}
+
+struct RawVisitor<B>(PhantomData<B>);
+
+impl<'de> de::Visitor<'de> for RawVisitor<Vec<u8>> {
+    type Value = RawBencode<Vec<u8>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("any valid BEncode value")
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> std::result::Result<Self::Value, E> {
+        Ok(RawBencode {
+            bytes: value.to_vec(),
+        })
+    }
+
+    fn visit_byte_buf<E>(self, value: Vec<u8>) -> std::result::Result<Self::Value, E> {
+        Ok(RawBencode { bytes: value })
+    }
+
+    /// Other deserializers don't know about raw values, so re-encode what they produce. The
+    /// result is canonical bencode, which may differ from the original input.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        let bytes = crate::to_bytes(&value).map_err(de::Error::custom)?;
+        Ok(RawBencode { bytes })
+    }
+}
+
+impl<'de: 'a, 'a> de::Visitor<'de> for RawVisitor<&'a [u8]> {
+    type Value = RawBencode<&'a [u8]>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a BEncode value borrowed from the input")
+    }
+
+    fn visit_borrowed_bytes<E>(self, value: &'de [u8]) -> std::result::Result<Self::Value, E> {
+        Ok(RawBencode { bytes: value })
+    }
+}
+
+impl<'de: 'a, 'a> de::Visitor<'de> for RawVisitor<Cow<'a, [u8]>> {
+    type Value = RawBencode<Cow<'a, [u8]>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("any valid BEncode value")
+    }
+
+    fn visit_borrowed_bytes<E>(self, value: &'de [u8]) -> std::result::Result<Self::Value, E> {
+        Ok(RawBencode {
+            bytes: Cow::Borrowed(value),
+        })
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> std::result::Result<Self::Value, E> {
+        Ok(RawBencode {
+            bytes: Cow::Owned(value.to_vec()),
+        })
+    }
+
+    fn visit_byte_buf<E>(self, value: Vec<u8>) -> std::result::Result<Self::Value, E> {
+        Ok(RawBencode {
+            bytes: Cow::Owned(value),
+        })
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let raw = RawVisitor::<Vec<u8>>(PhantomData).visit_newtype_struct(deserializer)?;
+        Ok(RawBencode {
+            bytes: Cow::Owned(raw.bytes),
+        })
+    }
+}
+
+impl<'de> de::Deserialize<'de> for RawBencode<Vec<u8>> {
+    fn deserialize<D: de::Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        d.deserialize_newtype_struct(TOKEN, RawVisitor::<Vec<u8>>(PhantomData))
+    }
+}
+
+impl<'de: 'a, 'a> de::Deserialize<'de> for RawBencode<&'a [u8]> {
+    fn deserialize<D: de::Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        d.deserialize_newtype_struct(TOKEN, RawVisitor::<&'a [u8]>(PhantomData))
+    }
+}
+
+impl<'de: 'a, 'a> de::Deserialize<'de> for RawBencode<Cow<'a, [u8]>> {
+    fn deserialize<D: de::Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        d.deserialize_newtype_struct(TOKEN, RawVisitor::<Cow<'a, [u8]>>(PhantomData))
+    }
+}
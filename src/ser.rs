@@ -1,16 +1,45 @@
 //! Serialize a Rust data structure into bencode data.
 
-mod string;
+pub(crate) mod string;
 
 use crate::error::{Error, Result};
+use crate::policy::{self, EnumPolicy, FloatPolicy, NonePolicy};
+use crate::raw;
+use crate::value;
 use serde::ser;
-use std::mem;
+use std::io;
+use std::ops::Range;
 use std::str;
 
 /// A structure for serializing Rust values into bencode.
+///
+/// By default the output is collected into a byte vector. Use [`Serializer::with_writer`] to
+/// write it to any [`io::Write`] instead.
 #[derive(Default, Debug)]
-pub struct Serializer {
-    buf: Vec<u8>,
+pub struct Serializer<W = Vec<u8>> {
+    writer: W,
+    float: FloatPolicy,
+    none: NonePolicy,
+    enums: EnumPolicy,
+    /// Whether the value being serialized is the value of a dictionary entry, which is left out
+    /// if the value is `None` or unit.
+    map_value: bool,
+    /// The tag and the name of an internally tagged newtype variant, to add as an entry to the
+    /// dictionary its contents serialize to.
+    tag_entry: Option<(&'static str, &'static str)>,
+    key_order: KeyOrder,
+}
+
+/// Which dictionaries of a value have their keys arrive sorted, in the order the dictionaries
+/// start. [`Serializer::stream`] records it in a first pass over the value, so that the second
+/// pass writes those dictionaries straight to the output instead of collecting them in memory.
+#[derive(Default, Debug)]
+struct KeyOrder {
+    /// Whether this is the first pass, which records the key order instead of following it.
+    recording: bool,
+    sorted: Vec<bool>,
+    /// Number of dictionaries started in the second pass.
+    next: usize,
 }
 
 impl Serializer {
@@ -23,33 +52,145 @@ impl Serializer {
     /// Consume the serializer and return the contents as a byte vector.
     #[must_use]
     pub fn into_vec(self) -> Vec<u8> {
-        self.buf
+        self.writer
+    }
+}
+
+impl<W: io::Write> Serializer<W> {
+    /// Create a new serializer writing to the given output stream.
+    pub fn with_writer(writer: W) -> Self {
+        Serializer {
+            writer,
+            float: FloatPolicy::default(),
+            none: NonePolicy::default(),
+            enums: EnumPolicy::default(),
+            map_value: false,
+            tag_entry: None,
+            key_order: KeyOrder::default(),
+        }
+    }
+
+    /// Serialize `value`, writing every dictionary whose keys arrive sorted straight to the
+    /// output.
+    ///
+    /// Serializing a value with [`serde::Serialize::serialize`] collects every dictionary in
+    /// memory until its last entry, so its keys can be sorted. This instead walks `value` twice:
+    /// the first pass writes nothing and finds the dictionaries whose keys arrive sorted, and the
+    /// second pass only collects the other dictionaries in memory. [`to_writer`] serializes
+    /// values this way.
+    ///
+    /// # Errors
+    ///
+    /// Serialization fails for the same reasons as [`to_writer`], and if the two passes over
+    /// `value` do not produce the same dictionaries.
+    pub fn stream<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
+        let mut probe = self.map_value(io::sink());
+        probe.map_value = self.map_value;
+        probe.key_order.recording = true;
+        value.serialize(&mut probe)?;
+
+        self.key_order = KeyOrder {
+            sorted: probe.key_order.sorted,
+            ..KeyOrder::default()
+        };
+        let result = value.serialize(&mut *self);
+        self.key_order = KeyOrder::default();
+        result
+    }
+
+    /// Consume the serializer and return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn push<T: AsRef<[u8]>>(&mut self, token: T) -> Result<()> {
+        self.writer
+            .write_all(token.as_ref())
+            .map_err(Error::IoError)
+    }
+}
+
+impl<W> Serializer<W> {
+    /// Set how floats are encoded. Defaults to [`FloatPolicy::Reject`], which makes serializing
+    /// a float fail.
+    #[must_use]
+    pub fn float_policy(mut self, policy: FloatPolicy) -> Self {
+        self.float = policy;
+        self
+    }
+
+    /// Set how `None` and unit values are encoded outside of a dictionary. Defaults to
+    /// [`NonePolicy::Omit`], which encodes them as nothing at all.
+    #[must_use]
+    pub fn none_policy(mut self, policy: NonePolicy) -> Self {
+        self.none = policy;
+        self
+    }
+
+    /// Set how enums are encoded. Defaults to [`EnumPolicy::ExternallyTagged`].
+    #[must_use]
+    pub fn enum_policy(mut self, policy: EnumPolicy) -> Self {
+        self.enums = policy;
+        self
+    }
+
+    /// A serializer with the same settings, writing the value of a dictionary entry to `writer`.
+    ///
+    /// It starts with no key order. Move the key order of `self` into it while it serializes a
+    /// nested value, and back afterwards.
+    fn map_value<V>(&self, writer: V) -> Serializer<V> {
+        Serializer {
+            writer,
+            float: self.float,
+            none: self.none,
+            enums: self.enums,
+            map_value: true,
+            tag_entry: None,
+            key_order: KeyOrder::default(),
+        }
+    }
+
+    /// Fails if the value being serialized is the contents of an internally tagged newtype
+    /// variant, for values that are not a dictionary the tag can be added to.
+    fn reject_tag_entry(&mut self) -> Result<()> {
+        match self.tag_entry.take() {
+            Some((tag, variant)) => Err(Error::InvalidValue(format!(
+                "Cannot serialize newtype variant `{variant}` with the tag `{tag}`: its contents \
+                 are not a dictionary"
+            ))),
+            None => Ok(()),
+        }
     }
 
-    fn push<T: AsRef<[u8]>>(&mut self, token: T) {
-        self.buf.extend_from_slice(token.as_ref());
+    /// The key of the dictionary entry wrapping the contents of a variant.
+    fn variant_key(&self, variant_index: u32, variant: &'static str) -> Vec<u8> {
+        match self.enums {
+            EnumPolicy::VariantIndex => variant_index.to_string().into_bytes(),
+            EnumPolicy::ExternallyTagged | EnumPolicy::InternallyTagged(_) => {
+                variant.as_bytes().to_vec()
+            }
+        }
     }
 }
 
 impl AsRef<[u8]> for Serializer {
     fn as_ref(&self) -> &[u8] {
-        self.buf.as_ref()
+        self.writer.as_ref()
     }
 }
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl<W: io::Write> ser::SerializeSeq for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
         value.serialize(&mut **self)
     }
     fn end(self) -> Result<()> {
-        self.push("e");
-        Ok(())
+        self.push("e")
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl<W: io::Write> ser::SerializeTuple for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
@@ -60,7 +201,7 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl<W: io::Write> ser::SerializeTupleStruct for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
@@ -71,54 +212,227 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl<W: io::Write> ser::SerializeTupleVariant for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
         value.serialize(&mut **self)
     }
     fn end(self) -> Result<()> {
-        self.push("ee");
-        Ok(())
+        self.push("ee")
     }
 }
 
+/// A dictionary entry collected by [`SerializeMap`].
+struct Entry {
+    /// The raw key, used for sorting.
+    key: Range<usize>,
+    /// The encoded key and value.
+    encoded: Range<usize>,
+}
+
+/// How [`SerializeMap`] writes the entries of a dictionary.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MapMode {
+    /// Collect the entries in memory, to sort them by key if needed.
+    Buffer,
+    /// Write the entries straight to the output, as the key order says they arrive sorted.
+    Stream,
+    /// Write the entries straight to the output of the first pass, which goes nowhere, and
+    /// record at this index of the key order whether they arrived sorted.
+    Record(usize),
+}
+
 #[doc(hidden)]
 // todo: This should be pub(crate).
-pub struct SerializeMap<'a> {
-    ser: &'a mut Serializer,
-    entries: Vec<(Vec<u8>, Vec<u8>)>,
+pub struct SerializeMap<'a, W> {
+    ser: &'a mut Serializer<W>,
+    mode: MapMode,
+    /// Whether the `d` starting the dictionary has been written, when streaming.
+    opened: bool,
+    /// The key of the last entry written, when streaming.
+    last_key: Option<Vec<u8>>,
+    /// The encoded entries, in the order they were added.
+    buf: Vec<u8>,
+    entries: Vec<Entry>,
+    /// Whether the entries arrived sorted by key, so `buf` can be written as is.
+    sorted: bool,
+    /// Whether the dictionary is the contents of a variant, within a dictionary that `end` closes
+    /// too.
+    wrapped: bool,
     cur_key: Option<Vec<u8>>,
 }
 
-impl<'a> SerializeMap<'a> {
-    pub fn new(ser: &'a mut Serializer, len: usize) -> SerializeMap<'_> {
+impl<'a, W: io::Write> SerializeMap<'a, W> {
+    pub fn new(ser: &'a mut Serializer<W>, len: usize) -> SerializeMap<'a, W> {
+        let order = &mut ser.key_order;
+        let mode = if order.recording {
+            order.sorted.push(true);
+            MapMode::Record(order.sorted.len() - 1)
+        } else {
+            let sorted = order.sorted.get(order.next).copied().unwrap_or(false);
+            order.next += 1;
+            if sorted {
+                MapMode::Stream
+            } else {
+                MapMode::Buffer
+            }
+        };
         SerializeMap {
             ser,
-            entries: Vec::with_capacity(len),
+            mode,
+            opened: false,
+            last_key: None,
+            buf: Vec::new(),
+            entries: if mode == MapMode::Buffer {
+                Vec::with_capacity(len)
+            } else {
+                Vec::new()
+            },
+            sorted: true,
+            wrapped: false,
             cur_key: None,
         }
     }
 
+    fn key(&self, entry: &Entry) -> &[u8] {
+        &self.buf[entry.key.clone()]
+    }
+
+    fn push_entry<T: ?Sized + ser::Serialize>(&mut self, key: &[u8], value: &T) -> Result<()> {
+        if self.mode != MapMode::Buffer {
+            return self.write_entry(key, value);
+        }
+        let start = self.buf.len();
+        let mut ser = self.ser.map_value(&mut self.buf);
+        ser.key_order = std::mem::take(&mut self.ser.key_order);
+        ser::Serializer::serialize_bytes(&mut ser, key)?;
+        let key_range = ser.writer.len() - key.len()..ser.writer.len();
+        let value_start = ser.writer.len();
+        let result = value.serialize(&mut ser);
+        self.ser.key_order = std::mem::take(&mut ser.key_order);
+        result?;
+
+        // Values that serialize to nothing, like `None`, are left out of the dictionary.
+        if self.buf.len() == value_start {
+            self.buf.truncate(start);
+            return Ok(());
+        }
+
+        let entry = Entry {
+            key: key_range,
+            encoded: start..self.buf.len(),
+        };
+        if let Some(last) = self.entries.last() {
+            self.sorted = self.sorted && self.key(last) < self.key(&entry);
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Writes an entry straight to the output.
+    fn write_entry<T: ?Sized + ser::Serialize>(&mut self, key: &[u8], value: &T) -> Result<()> {
+        if self.last_key.as_deref().is_some_and(|last| last >= key) {
+            if self.mode == MapMode::Stream {
+                return Err(Error::InvalidValue(format!(
+                    "Key `{}` arrived out of order in a dictionary whose keys arrived sorted \
+                     before",
+                    String::from_utf8_lossy(key)
+                )));
+            }
+            // The first pass writes to nowhere, so it only needs to remember this.
+            self.sorted = false;
+        }
+        if !self.opened {
+            self.ser.push("d")?;
+            self.opened = true;
+        }
+
+        let mut encoded_key = key.len().to_string().into_bytes();
+        encoded_key.push(b':');
+        encoded_key.extend_from_slice(key);
+        let settings = self.ser.map_value(());
+        let mut ser = settings.map_value(PendingKey {
+            writer: &mut self.ser.writer,
+            key: Some(encoded_key),
+        });
+        ser.key_order = std::mem::take(&mut self.ser.key_order);
+        let result = value.serialize(&mut ser);
+        let written = ser.writer.key.is_none();
+        self.ser.key_order = std::mem::take(&mut ser.key_order);
+        result?;
+
+        // Values that serialize to nothing, like `None`, leave out the entry.
+        if written {
+            self.last_key = Some(key.to_vec());
+        }
+        Ok(())
+    }
+
     fn end_map(&mut self) -> Result<()> {
         if self.cur_key.is_some() {
             return Err(Error::InvalidValue(
                 "`serialize_key` called without calling  `serialize_value`".to_string(),
             ));
         }
-        let mut entries = mem::take(&mut self.entries);
-        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
-        self.ser.push("d");
-        for (k, v) in entries {
-            ser::Serializer::serialize_bytes(&mut *self.ser, k.as_ref())?;
-            self.ser.push(v);
+        if self.mode != MapMode::Buffer {
+            if let MapMode::Record(index) = self.mode {
+                self.ser.key_order.sorted[index] = self.sorted;
+            }
+            if !self.opened {
+                self.ser.push("d")?;
+            }
+            return self.ser.push("e");
         }
-        self.ser.push("e");
-        Ok(())
+        self.ser.push("d")?;
+        if self.sorted {
+            self.ser.push(&self.buf)?;
+        } else {
+            let buf = &self.buf;
+            self.entries
+                .sort_by(|a, b| buf[a.key.clone()].cmp(&buf[b.key.clone()]));
+            if let Some(pair) = self
+                .entries
+                .windows(2)
+                .find(|pair| buf[pair[0].key.clone()] == buf[pair[1].key.clone()])
+            {
+                return Err(Error::DuplicateKey(format!(
+                    "Duplicate Key: `{}`",
+                    String::from_utf8_lossy(&buf[pair[0].key.clone()])
+                )));
+            }
+            for entry in &self.entries {
+                self.ser.push(&buf[entry.encoded.clone()])?;
+            }
+        }
+        self.ser.push("e")
     }
 }
 
-impl<'a> ser::SerializeMap for SerializeMap<'a> {
+/// Writes the encoded key of a dictionary entry ahead of the first output of its value, so that an
+/// entry whose value serializes to nothing is left out.
+struct PendingKey<'a> {
+    writer: &'a mut dyn io::Write,
+    key: Option<Vec<u8>>,
+}
+
+impl io::Write for PendingKey<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if let Some(key) = self.key.take() {
+            self.writer.write_all(&key)?;
+        }
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: io::Write> ser::SerializeMap for SerializeMap<'_, W> {
     type Ok = ();
     type Error = Error;
     fn serialize_key<T: ?Sized + ser::Serialize>(&mut self, key: &T) -> Result<()> {
@@ -137,13 +451,7 @@ impl<'a> ser::SerializeMap for SerializeMap<'a> {
                 "`serialize_value` called without calling `serialize_key`".to_string(),
             )
         })?;
-        let mut ser = Serializer::new();
-        value.serialize(&mut ser)?;
-        let value = ser.into_vec();
-        if !value.is_empty() {
-            self.entries.push((key, value));
-        }
-        Ok(())
+        self.push_entry(&key, value)
     }
     fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<()>
     where
@@ -157,20 +465,14 @@ impl<'a> ser::SerializeMap for SerializeMap<'a> {
             ));
         }
         let key = key.serialize(&mut string::Serializer)?;
-        let mut ser = Serializer::new();
-        value.serialize(&mut ser)?;
-        let value = ser.into_vec();
-        if !value.is_empty() {
-            self.entries.push((key, value));
-        }
-        Ok(())
+        self.push_entry(&key, value)
     }
     fn end(mut self) -> Result<()> {
         self.end_map()
     }
 }
 
-impl<'a> ser::SerializeStruct for SerializeMap<'a> {
+impl<W: io::Write> ser::SerializeStruct for SerializeMap<'_, W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + ser::Serialize>(
@@ -185,7 +487,7 @@ impl<'a> ser::SerializeStruct for SerializeMap<'a> {
     }
 }
 
-impl<'a> ser::SerializeStructVariant for SerializeMap<'a> {
+impl<W: io::Write> ser::SerializeStructVariant for SerializeMap<'_, W> {
     type Ok = ();
     type Error = Error;
     fn serialize_field<T: ?Sized + ser::Serialize>(
@@ -197,21 +499,23 @@ impl<'a> ser::SerializeStructVariant for SerializeMap<'a> {
     }
     fn end(mut self) -> Result<()> {
         self.end_map()?;
-        self.ser.push("e");
+        if self.wrapped {
+            self.ser.push("e")?;
+        }
         Ok(())
     }
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl<'a, W: io::Write> ser::Serializer for &'a mut Serializer<W> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = Self;
     type SerializeTuple = Self;
     type SerializeTupleStruct = Self;
     type SerializeTupleVariant = Self;
-    type SerializeMap = SerializeMap<'a>;
-    type SerializeStruct = SerializeMap<'a>;
-    type SerializeStructVariant = SerializeMap<'a>;
+    type SerializeMap = SerializeMap<'a, W>;
+    type SerializeStruct = SerializeMap<'a, W>;
+    type SerializeStructVariant = SerializeMap<'a, W>;
 
     fn serialize_bool(self, value: bool) -> Result<()> {
         self.serialize_i64(i64::from(value))
@@ -226,10 +530,14 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_i64(i64::from(value))
     }
     fn serialize_i64(self, value: i64) -> Result<()> {
-        self.push("i");
-        self.push(value.to_string());
-        self.push("e");
-        Ok(())
+        self.push("i")?;
+        self.push(value.to_string())?;
+        self.push("e")
+    }
+    fn serialize_i128(self, value: i128) -> Result<()> {
+        self.push("i")?;
+        self.push(value.to_string())?;
+        self.push("e")
     }
     fn serialize_u8(self, value: u8) -> Result<()> {
         self.serialize_u64(u64::from(value))
@@ -241,16 +549,34 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_u64(u64::from(value))
     }
     fn serialize_u64(self, value: u64) -> Result<()> {
-        self.push("i");
-        self.push(value.to_string());
-        self.push("e");
-        Ok(())
-    }
-    fn serialize_f32(self, _value: f32) -> Result<()> {
-        Err(Error::InvalidValue("Cannot serialize f32".to_string()))
+        self.push("i")?;
+        self.push(value.to_string())?;
+        self.push("e")
+    }
+    fn serialize_u128(self, value: u128) -> Result<()> {
+        self.push("i")?;
+        self.push(value.to_string())?;
+        self.push("e")
+    }
+    fn serialize_f32(self, value: f32) -> Result<()> {
+        match self.float {
+            FloatPolicy::Reject => Err(Error::InvalidValue("Cannot serialize f32".to_string())),
+            FloatPolicy::DecimalString => self.serialize_str(&value.to_string()),
+            FloatPolicy::ScaledInteger(places) => {
+                self.serialize_i64(policy::to_scaled(f64::from(value), places)?)
+            }
+            FloatPolicy::Ieee754 => self.serialize_bytes(&value.to_be_bytes()),
+        }
     }
-    fn serialize_f64(self, _value: f64) -> Result<()> {
-        Err(Error::InvalidValue("Cannot serialize f64".to_string()))
+    fn serialize_f64(self, value: f64) -> Result<()> {
+        match self.float {
+            FloatPolicy::Reject => Err(Error::InvalidValue("Cannot serialize f64".to_string())),
+            FloatPolicy::DecimalString => self.serialize_str(&value.to_string()),
+            FloatPolicy::ScaledInteger(places) => {
+                self.serialize_i64(policy::to_scaled(value, places)?)
+            }
+            FloatPolicy::Ieee754 => self.serialize_bytes(&value.to_be_bytes()),
+        }
     }
     fn serialize_char(self, value: char) -> Result<()> {
         let mut buffer = [0; 4];
@@ -261,13 +587,21 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_bytes(value.as_bytes())
     }
     fn serialize_bytes(self, value: &[u8]) -> Result<()> {
-        self.push(value.len().to_string());
-        self.push(":");
-        self.push(value);
-        Ok(())
+        self.push(value.len().to_string())?;
+        self.push(":")?;
+        self.push(value)
     }
     fn serialize_unit(self) -> Result<()> {
-        Ok(())
+        if self.map_value {
+            // The dictionary entry is left out instead.
+            return Ok(());
+        }
+        match self.none {
+            NonePolicy::Reject => Err(Error::InvalidValue(
+                "Cannot serialize `None` or unit outside of a dictionary".to_string(),
+            )),
+            policy => self.push(policy.encoded().unwrap_or_default()),
+        }
     }
     fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
         self.serialize_unit()
@@ -275,39 +609,74 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_unit_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
-        self.serialize_str(variant)
+        match self.enums {
+            EnumPolicy::VariantIndex => self.serialize_u32(variant_index),
+            EnumPolicy::ExternallyTagged | EnumPolicy::InternallyTagged(_) => {
+                self.serialize_str(variant)
+            }
+        }
     }
     fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<()> {
+        if name == raw::TOKEN {
+            // The raw bytes are already bencoded, so splice them in verbatim.
+            let raw = value.serialize(&mut string::Serializer)?;
+            if raw.is_empty() {
+                return Err(Error::InvalidValue(
+                    "Raw bencode must hold a value, not empty bytes".to_string(),
+                ));
+            }
+            return self.push(raw);
+        }
+        if name == value::BIG_INT_TOKEN {
+            // The digits of an integer too large for a `u128`.
+            let digits = value.serialize(&mut string::Serializer)?;
+            self.push("i")?;
+            self.push(digits)?;
+            return self.push("e");
+        }
         value.serialize(self)
     }
     fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
         value: &T,
     ) -> Result<()> {
-        self.push("d");
-        self.serialize_bytes(variant.as_bytes())?;
+        self.reject_tag_entry()?;
+        if let EnumPolicy::InternallyTagged(tag) = self.enums {
+            // The dictionary the contents serialize to takes the tag as its first entry.
+            self.tag_entry = Some((tag, variant));
+            self.map_value = false;
+            value.serialize(&mut *self)?;
+            return self.reject_tag_entry();
+        }
+        let key = self.variant_key(variant_index, variant);
+        self.push("d")?;
+        self.serialize_bytes(&key)?;
+        self.map_value = false;
         value.serialize(&mut *self)?;
-        self.push("e");
-        Ok(())
+        self.push("e")
     }
     fn serialize_none(self) -> Result<()> {
-        Ok(())
+        self.serialize_unit()
     }
     fn serialize_some<T: ?Sized + ser::Serialize>(self, value: &T) -> Result<()> {
+        // Only the outermost `None` of a dictionary value leaves out the entry.
+        self.map_value = false;
         value.serialize(self)
     }
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self> {
-        self.push("l");
+        self.reject_tag_entry()?;
+        self.map_value = false;
+        self.push("l")?;
         Ok(self)
     }
     fn serialize_tuple(self, size: usize) -> Result<Self> {
@@ -319,17 +688,30 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_tuple_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        self.push("d");
-        self.serialize_bytes(variant.as_bytes())?;
-        self.push("l");
+        self.reject_tag_entry()?;
+        if let EnumPolicy::InternallyTagged(tag) = self.enums {
+            return Err(Error::InvalidValue(format!(
+                "Cannot serialize tuple variant `{variant}` with the tag `{tag}`"
+            )));
+        }
+        let key = self.variant_key(variant_index, variant);
+        self.map_value = false;
+        self.push("d")?;
+        self.serialize_bytes(&key)?;
+        self.push("l")?;
         Ok(self)
     }
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
-        Ok(SerializeMap::new(self, len.unwrap_or(0)))
+        let tag_entry = self.tag_entry.take();
+        let mut map = SerializeMap::new(self, len.unwrap_or(0));
+        if let Some((tag, variant)) = tag_entry {
+            ser::SerializeMap::serialize_entry(&mut map, tag, variant)?;
+        }
+        Ok(map)
     }
     fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
         self.serialize_map(Some(len))
@@ -337,13 +719,22 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_struct_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.push("d");
-        self.serialize_bytes(variant.as_bytes())?;
-        Ok(SerializeMap::new(self, len))
+        self.reject_tag_entry()?;
+        if let EnumPolicy::InternallyTagged(tag) = self.enums {
+            let mut map = SerializeMap::new(self, len + 1);
+            ser::SerializeMap::serialize_entry(&mut map, tag, variant)?;
+            return Ok(map);
+        }
+        let key = self.variant_key(variant_index, variant);
+        self.push("d")?;
+        self.serialize_bytes(&key)?;
+        let mut map = SerializeMap::new(self, len);
+        map.wrapped = true;
+        Ok(map)
     }
 }
 
@@ -421,3 +812,43 @@ pub fn to_string<T: ser::Serialize>(b: &T) -> Result<String> {
         .map(std::string::ToString::to_string)
         .map_err(|_| Error::InvalidValue("Not an UTF-8".to_string()))
 }
+
+/// Serialize the given data as bencode into the given output stream.
+///
+/// Output is written as it is produced, except that a dictionary whose keys arrive out of order
+/// is collected in memory until its last entry so its keys can be sorted. To tell those apart,
+/// `value` is walked twice, as with [`Serializer::stream`].
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_derive::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+/// struct Address {
+///     street: String,
+///     city: String,
+/// }
+///
+/// let address = Address {
+///     street: "1313 Webfoot Walk".to_string(),
+///     city: "Duckburg, Calisota".to_string(),
+/// };
+///
+/// let mut file = Vec::new();
+/// serde_bencode::to_writer(&mut file, &address)?;
+/// assert_eq!(
+///     file,
+///     b"d4:city18:Duckburg, Calisota6:street17:1313 Webfoot Walke",
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to fail, `T` contains
+/// floating point values, which bencode cannot serialize, or writing to `writer` fails.
+pub fn to_writer<W: io::Write, T: ?Sized + ser::Serialize>(writer: W, value: &T) -> Result<()> {
+    Serializer::with_writer(writer).stream(value)
+}
@@ -21,10 +21,14 @@ fn unexpected<T>(unexp: de::Unexpected<'_>) -> Result<T> {
 /// StringSerializer for serializing *just* strings (bytes are also strings in bencode).
 /// The string is returned as Result<Vec<u8>>::Ok without any prefixing (without bencode string
 /// length prefix).
+///
+/// This is also the serializer for dictionary keys, so integers are accepted and written in
+/// decimal, as are newtype structs and unit variants, which are written as their contents and
+/// their name.
 // todo: This should be pub(crate).
 pub struct Serializer;
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl ser::Serializer for &mut Serializer {
     type Ok = Vec<u8>;
     type Error = Error;
     type SerializeSeq = ser::Impossible<Vec<u8>, Error>;
@@ -48,7 +52,10 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_i64(i64::from(value))
     }
     fn serialize_i64(self, value: i64) -> Result<Vec<u8>> {
-        unexpected(de::Unexpected::Signed(value))
+        Ok(value.to_string().into_bytes())
+    }
+    fn serialize_i128(self, value: i128) -> Result<Vec<u8>> {
+        Ok(value.to_string().into_bytes())
     }
     fn serialize_u8(self, value: u8) -> Result<Vec<u8>> {
         self.serialize_u64(u64::from(value))
@@ -60,7 +67,10 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_u64(u64::from(value))
     }
     fn serialize_u64(self, value: u64) -> Result<Vec<u8>> {
-        unexpected(de::Unexpected::Unsigned(value))
+        Ok(value.to_string().into_bytes())
+    }
+    fn serialize_u128(self, value: u128) -> Result<Vec<u8>> {
+        Ok(value.to_string().into_bytes())
     }
     fn serialize_f32(self, value: f32) -> Result<Vec<u8>> {
         self.serialize_f64(f64::from(value))
@@ -69,7 +79,8 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         unexpected(de::Unexpected::Float(value))
     }
     fn serialize_char(self, value: char) -> Result<Vec<u8>> {
-        self.serialize_bytes(&[value as u8])
+        let mut buffer = [0; 4];
+        self.serialize_bytes(value.encode_utf8(&mut buffer).as_bytes())
     }
     fn serialize_str(self, value: &str) -> Result<Vec<u8>> {
         self.serialize_bytes(value.as_bytes())
@@ -87,16 +98,16 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Vec<u8>> {
-        unexpected(de::Unexpected::UnitVariant)
+        self.serialize_str(variant)
     }
     fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(
         self,
         _name: &'static str,
-        _value: &T,
+        value: &T,
     ) -> Result<Vec<u8>> {
-        unexpected(de::Unexpected::NewtypeStruct)
+        value.serialize(self)
     }
     fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(
         self,
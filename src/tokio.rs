@@ -0,0 +1,169 @@
+//! Decode and encode bencode asynchronously with [`tokio`].
+//!
+//! Requires the `tokio` feature.
+
+use crate::de::tokenizer::{ValueBuffer, ValueScanner};
+use crate::de::DecodeLimits;
+use crate::error::{Error, Result};
+use ::bytes::{BufMut, BytesMut};
+use ::tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use ::tokio_util::codec::{Decoder, Encoder};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Deserialize an instance of type `T` from an asynchronous input stream.
+///
+/// The input is tokenized as it arrives, and handed to serde once a complete value has been read.
+/// Exactly the bytes of that value are consumed from `reader`, so whatever follows can be read
+/// from it afterwards. Wrap a plain [`AsyncRead`](::tokio::io::AsyncRead) in a
+/// [`BufReader`](::tokio::io::BufReader) to use it here.
+///
+/// # Examples
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), serde_bencode::Error> {
+/// // cspell:disable-next-line
+/// let mut input = &b"d1:y1:qei1e"[..];
+///
+/// let message: serde_bencode::value::Value =
+///     serde_bencode::tokio::from_async_reader(&mut input).await?;
+///
+/// assert_eq!(message["y"].as_str(), Some("q"));
+/// assert_eq!(input, b"i1e");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This fails if reading from `reader` fails, if the input ends before the value is complete, or
+/// for the same reasons as [`crate::from_bytes`].
+pub async fn from_async_reader<R, T>(reader: R) -> Result<T>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+{
+    from_async_reader_with_limits(reader, DecodeLimits::default()).await
+}
+
+/// Deserialize an instance of type `T` from an asynchronous input stream, like
+/// [`from_async_reader`], with the given limits instead of [`DecodeLimits::default`].
+///
+/// # Errors
+///
+/// This fails like [`from_async_reader`], and with [`Error::LimitExceeded`] if the value exceeds
+/// the limits.
+pub async fn from_async_reader_with_limits<R, T>(mut reader: R, limits: DecodeLimits) -> Result<T>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut value = ValueBuffer::new(limits);
+    loop {
+        let chunk = reader.fill_buf().await?;
+        let (taken, complete) = value.push(chunk)?;
+        reader.consume(taken);
+        if complete {
+            return value.deserialize();
+        }
+    }
+}
+
+/// Serialize the given data as bencode into an asynchronous output stream.
+///
+/// # Errors
+///
+/// This fails for the same reasons as [`crate::to_writer`].
+pub async fn to_async_writer<W, T>(mut writer: W, value: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: ?Sized + Serialize,
+{
+    let mut bytes = Vec::new();
+    crate::to_writer(&mut bytes, value)?;
+    writer.write_all(&bytes).await.map_err(Error::IoError)
+}
+
+/// A [`tokio_util::codec`] codec that frames a byte stream as a sequence of
+/// bencoded values, decoding them into `T` and encoding anything serializable.
+///
+/// # Examples
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::tokio::BencodeCodec;
+/// use serde_bencode::value::Value;
+/// use tokio_util::codec::Decoder;
+///
+/// let mut codec = BencodeCodec::<Value>::new();
+/// // cspell:disable-next-line
+/// let mut buf = bytes::BytesMut::from(&b"i1ed1:y"[..]);
+///
+/// assert_eq!(codec.decode(&mut buf)?, Some(Value::Int(1)));
+/// assert_eq!(codec.decode(&mut buf)?, None);
+/// buf.extend_from_slice(b"1:qe");
+/// assert_eq!(codec.decode(&mut buf)?.unwrap()["y"].as_str(), Some("q"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct BencodeCodec<T> {
+    scanner: ValueScanner,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<T> BencodeCodec<T> {
+    /// Create a codec with the default [`DecodeLimits`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_limits(DecodeLimits::default())
+    }
+
+    /// Create a codec that applies the given limits to each decoded value.
+    #[must_use]
+    pub fn with_limits(limits: DecodeLimits) -> Self {
+        BencodeCodec {
+            scanner: ValueScanner::new(limits),
+            item: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for BencodeCodec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for BencodeCodec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BencodeCodec")
+            .field("scanner", &self.scanner)
+            .finish()
+    }
+}
+
+impl<T: DeserializeOwned> Decoder for BencodeCodec<T> {
+    type Item = T;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>> {
+        match self.scanner.scan(src)? {
+            Some(len) => {
+                crate::de::from_slice_with_limits(&src.split_to(len), self.scanner.limits())
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T, S: Serialize> Encoder<S> for BencodeCodec<T> {
+    type Error = Error;
+
+    fn encode(&mut self, item: S, dst: &mut BytesMut) -> Result<()> {
+        let len = dst.len();
+        crate::to_writer(dst.writer(), &item).inspect_err(|_| dst.truncate(len))
+    }
+}
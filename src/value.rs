@@ -1,10 +1,25 @@
 //! Structures for representing bencoded values with Rust data types.
 
+mod bigint;
+mod deserializer;
+mod extra;
+mod index;
+mod serializer;
+
+pub use self::bigint::BigInt;
+pub(crate) use self::bigint::TOKEN as BIG_INT_TOKEN;
+pub use self::deserializer::from_value;
+pub use self::extra::Extra;
+pub use self::index::Index;
+pub use self::serializer::{to_value, Serializer};
+
 use serde::de;
 use serde::ser::{self, SerializeMap, SerializeSeq};
 use serde_bytes::{ByteBuf, Bytes};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 use std::fmt;
+use std::str;
 
 /// All possible values which may be serialized in bencode.
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -15,11 +30,209 @@ pub enum Value {
     /// An integer.
     Int(i64),
 
+    /// An integer that does not fit in an `i64`.
+    BigInt(BigInt),
+
     /// A list of other bencoded values.
     List(Vec<Value>),
 
-    /// A map of (key, value) pairs.
-    Dict(HashMap<Vec<u8>, Value>),
+    /// A map of (key, value) pairs, sorted by the raw bytes of the keys.
+    Dict(BTreeMap<Vec<u8>, Value>),
+
+    /// The absence of a value, which indexing returns for a missing key or index. Bencode has no
+    /// null, so this is never decoded, and serializing it fails.
+    Null,
+}
+
+impl Value {
+    /// Returns true if this is [`Value::Null`], as returned when indexing with a missing key or
+    /// index.
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(*self, Value::Null)
+    }
+
+    /// If the value is an integer, returns it.
+    #[must_use]
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            Value::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// If the value is an integer that fits in a `u64`, returns it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use serde_bencode::value::Value;
+    /// let value: Value = serde_bencode::from_bytes(b"i18446744073709551615e").unwrap();
+    ///
+    /// assert_eq!(value.as_u64(), Some(u64::MAX));
+    /// assert_eq!(value.as_int(), None);
+    /// ```
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Int(i) => u64::try_from(i).ok(),
+            Value::BigInt(ref i) => i.to_u64(),
+            _ => None,
+        }
+    }
+
+    /// If the value is an integer that does not fit in an `i64`, returns it.
+    #[must_use]
+    pub fn as_big_int(&self) -> Option<&BigInt> {
+        match *self {
+            Value::BigInt(ref i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// If the value is a byte string, returns its bytes.
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            Value::Bytes(ref b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// If the value is a byte string holding valid UTF-8, returns it as a string slice.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes().and_then(|b| str::from_utf8(b).ok())
+    }
+
+    /// If the value is a list, returns its elements.
+    #[must_use]
+    pub fn as_list(&self) -> Option<&Vec<Value>> {
+        match *self {
+            Value::List(ref l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// If the value is a dictionary, returns its entries.
+    #[must_use]
+    pub fn as_dict(&self) -> Option<&BTreeMap<Vec<u8>, Value>> {
+        match *self {
+            Value::Dict(ref d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// If the value is an integer, returns a mutable reference to it.
+    pub fn as_int_mut(&mut self) -> Option<&mut i64> {
+        match *self {
+            Value::Int(ref mut i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// If the value is a byte string, returns a mutable reference to its bytes.
+    pub fn as_bytes_mut(&mut self) -> Option<&mut Vec<u8>> {
+        match *self {
+            Value::Bytes(ref mut b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// If the value is a byte string holding valid UTF-8, returns it as a mutable string slice.
+    pub fn as_str_mut(&mut self) -> Option<&mut str> {
+        self.as_bytes_mut().and_then(|b| str::from_utf8_mut(b).ok())
+    }
+
+    /// If the value is a list, returns a mutable reference to its elements.
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match *self {
+            Value::List(ref mut l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// If the value is a dictionary, returns a mutable reference to its entries.
+    pub fn as_dict_mut(&mut self) -> Option<&mut BTreeMap<Vec<u8>, Value>> {
+        match *self {
+            Value::Dict(ref mut d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Looks up a dictionary entry by key, or a list element by index.
+    ///
+    /// Returns `None` if the key or index is missing, or the value is not a dictionary or list.
+    ///
+    /// # Examples
+    /// ```
+    /// # use serde_bencode::value::Value;
+    /// // cspell:disable-next-line
+    /// let value: Value = serde_bencode::from_bytes(b"d1:xli1ei2eee").unwrap();
+    ///
+    /// assert_eq!(value.get("x").and_then(|x| x.get(1)), Some(&Value::Int(2)));
+    /// assert_eq!(value.get("y"), None);
+    /// ```
+    pub fn get<I: Index>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+
+    /// Mutable version of [`Value::get`].
+    pub fn get_mut<I: Index>(&mut self, index: I) -> Option<&mut Value> {
+        index.index_into_mut(self)
+    }
+
+    /// Looks up a nested value by a JSON Pointer (RFC 6901) style path, such as
+    /// `/info/files/0/length`.
+    ///
+    /// Each `/`-separated token is a dictionary key, or a list index for lists. Within a token,
+    /// `~1` stands for `/` and `~0` for `~`. The empty pointer refers to the value itself.
+    ///
+    /// # Examples
+    /// ```
+    /// # use serde_bencode::value::Value;
+    /// // cspell:disable-next-line
+    /// let value: Value = serde_bencode::from_bytes(b"d4:infod5:filesld6:lengthi8eeeee").unwrap();
+    ///
+    /// assert_eq!(value.pointer("/info/files/0/length"), Some(&Value::Int(8)));
+    /// assert_eq!(value.pointer("/info/files/1/length"), None);
+    /// ```
+    #[must_use]
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        pointer_tokens(pointer)?.try_fold(self, |target, token| match target {
+            Value::Dict(dict) => dict.get(token.as_bytes()),
+            Value::List(list) => parse_list_index(&token).and_then(|i| list.get(i)),
+            _ => None,
+        })
+    }
+
+    /// Mutable version of [`Value::pointer`].
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        pointer_tokens(pointer)?.try_fold(self, |target, token| match target {
+            Value::Dict(dict) => dict.get_mut(token.as_bytes()),
+            Value::List(list) => parse_list_index(&token).and_then(move |i| list.get_mut(i)),
+            _ => None,
+        })
+    }
+}
+
+/// Splits a JSON Pointer into its unescaped tokens, or returns `None` if it is malformed.
+fn pointer_tokens(pointer: &str) -> Option<impl Iterator<Item = String> + '_> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return None;
+    }
+    Some(
+        pointer
+            .split('/')
+            .skip(1)
+            .map(|token| token.replace("~1", "/").replace("~0", "~")),
+    )
+}
+
+fn parse_list_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.starts_with('0') && token.len() > 1) {
+        return None;
+    }
+    token.parse().ok()
 }
 
 impl ser::Serialize for Value {
@@ -31,6 +244,7 @@ impl ser::Serialize for Value {
         match *self {
             Value::Bytes(ref v) => s.serialize_bytes(v),
             Value::Int(v) => s.serialize_i64(v),
+            Value::BigInt(ref v) => ser::Serialize::serialize(v, s),
             Value::List(ref v) => {
                 let mut seq = s.serialize_seq(Some(v.len()))?;
                 for e in v {
@@ -45,6 +259,7 @@ impl ser::Serialize for Value {
                 }
                 map.end()
             }
+            Value::Null => Err(ser::Error::custom("a missing value cannot be serialized")),
         }
     }
 }
@@ -63,10 +278,27 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
         Ok(Value::Int(value))
     }
 
-    #[allow(clippy::cast_possible_wrap)]
     #[inline]
     fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
-        Ok(Value::Int(value as i64))
+        Ok(value.into())
+    }
+
+    #[inline]
+    fn visit_i128<E>(self, value: i128) -> Result<Value, E> {
+        Ok(BigInt::from(value).into())
+    }
+
+    #[inline]
+    fn visit_u128<E>(self, value: u128) -> Result<Value, E> {
+        Ok(BigInt::from(value).into())
+    }
+
+    /// Integers too large for a `u128` arrive as a newtype struct holding their digits.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        de::Deserialize::deserialize(deserializer).map(|i: BigInt| i.into())
     }
 
     #[inline]
@@ -104,7 +336,7 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
     where
         V: de::MapAccess<'de>,
     {
-        let mut map = HashMap::new();
+        let mut map = BTreeMap::new();
         while let Some((k, v)) = access.next_entry::<ByteBuf, _>()? {
             map.insert(k.into_vec(), v);
         }
@@ -128,18 +360,82 @@ impl From<i64> for Value {
     }
 }
 
+macro_rules! impl_from_int {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(v: $ty) -> Value {
+                    Value::Int(i64::from(v))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8 i16 i32 u8 u16 u32);
+
+impl From<BigInt> for Value {
+    /// Converts to a [`Value::Int`] if the integer fits in an `i64`.
+    fn from(v: BigInt) -> Value {
+        match v.to_i64() {
+            Some(i) => Value::Int(i),
+            None => Value::BigInt(v),
+        }
+    }
+}
+
+impl From<u64> for Value {
+    fn from(v: u64) -> Value {
+        match i64::try_from(v) {
+            Ok(i) => Value::Int(i),
+            Err(_) => Value::BigInt(v.into()),
+        }
+    }
+}
+
+impl From<i128> for Value {
+    fn from(v: i128) -> Value {
+        BigInt::from(v).into()
+    }
+}
+
+impl From<u128> for Value {
+    fn from(v: u128) -> Value {
+        BigInt::from(v).into()
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl From<num_bigint::BigInt> for Value {
+    fn from(v: num_bigint::BigInt) -> Value {
+        BigInt::from(v).into()
+    }
+}
+
 impl From<String> for Value {
     fn from(s: String) -> Value {
         Value::Bytes(s.into_bytes())
     }
 }
 
-impl<'a> From<&'a str> for Value {
+impl From<&str> for Value {
     fn from(v: &str) -> Value {
         Value::Bytes(v.as_bytes().to_vec())
     }
 }
 
+impl From<&[u8]> for Value {
+    fn from(v: &[u8]) -> Value {
+        Value::Bytes(v.to_vec())
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for Value {
+    fn from(v: &[u8; N]) -> Value {
+        Value::Bytes(v.to_vec())
+    }
+}
+
 impl From<Vec<u8>> for Value {
     fn from(v: Vec<u8>) -> Value {
         Value::Bytes(v)
@@ -152,15 +448,21 @@ impl From<Vec<Value>> for Value {
     }
 }
 
+impl From<BTreeMap<Vec<u8>, Value>> for Value {
+    fn from(v: BTreeMap<Vec<u8>, Value>) -> Value {
+        Value::Dict(v)
+    }
+}
+
 impl From<HashMap<Vec<u8>, Value>> for Value {
     fn from(v: HashMap<Vec<u8>, Value>) -> Value {
-        Value::Dict(v)
+        Value::Dict(v.into_iter().collect())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::BTreeMap;
 
     use serde_test::{assert_tokens, Token};
 
@@ -189,6 +491,12 @@ mod tests {
         assert_tokens(&int, &[Token::I64(1)]);
     }
 
+    #[test]
+    fn test_ser_de_u64() {
+        let int = Value::from(u64::MAX);
+        assert_tokens(&int, &[Token::U64(u64::MAX)]);
+    }
+
     #[test]
     fn test_ser_de_list() {
         let int = Value::List(vec![Value::Int(1)]);
@@ -201,7 +509,7 @@ mod tests {
 
     #[test]
     fn test_ser_de_dict() {
-        let dict = Value::Dict(HashMap::from([(b"key".to_vec(), Value::Int(1))]));
+        let dict = Value::Dict(BTreeMap::from([(b"key".to_vec(), Value::Int(1))]));
 
         assert_tokens(
             &dict,
@@ -214,8 +522,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_dict_keys_are_sorted_by_raw_bytes() {
+        // cspell: disable-next-line
+        let value: Value = crate::from_bytes(b"d1:bi1e1:ai2e1:Bi3e2:\xff\x00i4ee").unwrap();
+
+        let Value::Dict(dict) = value else {
+            panic!("expected a dict")
+        };
+        let keys: Vec<&[u8]> = dict.keys().map(Vec::as_slice).collect();
+        assert_eq!(keys, vec![&b"B"[..], b"a", b"b", b"\xff\x00"]);
+    }
+
     mod it_should_be_converted_from {
-        use std::collections::HashMap;
+        use std::collections::{BTreeMap, HashMap};
 
         use crate::value::Value;
 
@@ -243,19 +563,144 @@ mod tests {
             assert_eq!(value, Value::Bytes(b"11".to_vec()));
         }
 
+        #[test]
+        fn a_smaller_integer() {
+            let value: Value = 11u16.into();
+            assert_eq!(value, Value::Int(11));
+        }
+
+        #[test]
+        fn a_byte_slice() {
+            let value: Value = b"11"[..].into();
+            assert_eq!(value, Value::Bytes(b"11".to_vec()));
+        }
+
+        #[test]
+        fn a_byte_array_reference() {
+            let value: Value = b"11".into();
+            assert_eq!(value, Value::Bytes(b"11".to_vec()));
+        }
+
         #[test]
         fn a_vector_of_other_values() {
             let value: Value = vec![Value::Bytes(b"11".to_vec())].into();
             assert_eq!(value, Value::List(vec!(Value::Bytes(b"11".to_vec()))));
         }
 
+        #[test]
+        fn a_btree_map_of_other_values() {
+            let value: Value = BTreeMap::from([(b"key".to_vec(), Value::Int(3))]).into();
+            assert_eq!(
+                value,
+                Value::Dict(BTreeMap::from([(b"key".to_vec(), Value::Int(3))]))
+            );
+        }
+
         #[test]
         fn a_hash_map_of_other_values() {
             let value: Value = HashMap::from([(b"key".to_vec(), Value::Int(3))]).into();
             assert_eq!(
                 value,
-                Value::Dict(HashMap::from([(b"key".to_vec(), Value::Int(3))]))
+                Value::Dict(BTreeMap::from([(b"key".to_vec(), Value::Int(3))]))
+            );
+        }
+    }
+
+    mod it_should_allow_accessing {
+        use std::collections::BTreeMap;
+
+        use crate::value::Value;
+        use crate::{from_bytes, from_value};
+
+        fn torrent() -> Value {
+            // cspell: disable-next-line
+            from_bytes(b"d4:infod5:filesld6:lengthi8e4:pathl1:aeee4:name3:foo3:a/bi1e3:c~di2eee")
+                .unwrap()
+        }
+
+        #[test]
+        fn scalars_with_typed_accessors() {
+            let value = torrent();
+
+            assert_eq!(value["info"]["name"].as_str(), Some("foo"));
+            assert_eq!(value["info"]["name"].as_bytes(), Some(&b"foo"[..]));
+            assert_eq!(value["info"]["name"].as_int(), None);
+            assert_eq!(value["info"]["files"][0]["length"].as_int(), Some(8));
+            assert_eq!(Value::Bytes(vec![0xff]).as_str(), None);
+        }
+
+        #[test]
+        fn containers_with_typed_accessors() {
+            let value = torrent();
+
+            assert_eq!(value["info"]["files"].as_list().map(Vec::len), Some(1));
+            assert_eq!(value["info"].as_dict().map(BTreeMap::len), Some(4));
+            assert_eq!(value["info"].as_list(), None);
+        }
+
+        #[test]
+        fn missing_entries_through_the_null_sentinel() {
+            let value = torrent();
+            let missing = &value["info"]["files"][3]["length"];
+
+            assert!(missing.is_null());
+            assert_eq!(missing.as_int(), None);
+            assert_eq!(missing.as_list(), None);
+            assert!(value["info"]["name"][0].is_null());
+            assert!(!Value::List(vec![]).is_null());
+            assert!(crate::to_bytes(missing).is_err());
+            assert_eq!(from_value::<Option<i64>>(missing.clone()).unwrap(), None);
+        }
+
+        #[test]
+        fn entries_with_get() {
+            let value = torrent();
+
+            assert_eq!(
+                value.get("info").and_then(|i| i.get("name")),
+                Some(&Value::from("foo"))
+            );
+            assert!(value.get(&b"info"[..]).is_some());
+            assert_eq!(value.get("nope"), None);
+            assert_eq!(value.get(0), None);
+        }
+
+        #[test]
+        fn entries_mutably() {
+            let mut value = torrent();
+
+            *value.pointer_mut("/info/files/0/length").unwrap() = Value::Int(9);
+            value
+                .get_mut("info")
+                .and_then(Value::as_dict_mut)
+                .unwrap()
+                .remove(&b"name"[..]);
+            value
+                .get_mut("info")
+                .and_then(|i| i.get_mut("files"))
+                .and_then(Value::as_list_mut)
+                .unwrap()
+                .push(Value::Int(1));
+
+            assert_eq!(value.pointer("/info/files/0/length"), Some(&Value::Int(9)));
+            assert!(value["info"]["name"].is_null());
+            assert_eq!(value["info"]["files"][1].as_int(), Some(1));
+        }
+
+        #[test]
+        fn entries_by_pointer() {
+            let value = torrent();
+
+            assert_eq!(value.pointer(""), Some(&value));
+            assert_eq!(
+                value.pointer("/info/files/0/path/0"),
+                Some(&Value::from("a"))
             );
+            assert_eq!(value.pointer("/info/a~1b"), Some(&Value::Int(1)));
+            assert_eq!(value.pointer("/info/c~0d"), Some(&Value::Int(2)));
+            assert_eq!(value.pointer("/info/files/00"), None);
+            assert_eq!(value.pointer("/info/files/1"), None);
+            assert_eq!(value.pointer("info"), None);
         }
     }
 
@@ -555,7 +1000,7 @@ mod tests {
 
                 mod empty {
 
-                    use std::collections::HashMap;
+                    use std::collections::BTreeMap;
 
                     use serde::Serialize;
 
@@ -569,7 +1014,7 @@ mod tests {
                     fn serialization() {
                         let mut ser = Serializer::new();
 
-                        let value = Value::List(vec![Value::Dict(HashMap::new())]);
+                        let value = Value::List(vec![Value::Dict(BTreeMap::new())]);
                         let _unused = value.serialize(&mut ser);
 
                         // cspell: disable-next-line
@@ -581,12 +1026,12 @@ mod tests {
                         // cspell: disable-next-line
                         let value: Value = from_bytes(b"ldee").unwrap();
 
-                        assert_eq!(value, Value::List(vec![Value::Dict(HashMap::new())]));
+                        assert_eq!(value, Value::List(vec![Value::Dict(BTreeMap::new())]));
                     }
                 }
 
                 mod non_empty {
-                    use std::collections::HashMap;
+                    use std::collections::BTreeMap;
 
                     use serde::Serialize;
 
@@ -600,7 +1045,7 @@ mod tests {
                     fn serialization() {
                         let mut ser = Serializer::new();
 
-                        let value = Value::List(vec![Value::Dict(HashMap::from([(
+                        let value = Value::List(vec![Value::Dict(BTreeMap::from([(
                             b"key".to_vec(),
                             Value::Int(3),
                         )]))]);
@@ -617,7 +1062,7 @@ mod tests {
 
                         assert_eq!(
                             value,
-                            Value::List(vec![Value::Dict(HashMap::from([(
+                            Value::List(vec![Value::Dict(BTreeMap::from([(
                                 b"key".to_vec(),
                                 Value::Int(3),
                             )]))])
@@ -630,7 +1075,7 @@ mod tests {
         mod dictionary {
 
             mod empty {
-                use std::collections::HashMap;
+                use std::collections::BTreeMap;
 
                 use serde::Serialize;
 
@@ -644,7 +1089,7 @@ mod tests {
                 fn serialization() {
                     let mut ser = Serializer::new();
 
-                    let value = Value::Dict(HashMap::new());
+                    let value = Value::Dict(BTreeMap::new());
                     let _unused = value.serialize(&mut ser);
 
                     assert_bytes_eq(ser.as_ref(), b"de");
@@ -654,13 +1099,13 @@ mod tests {
                 fn deserialization() {
                     let value: Value = from_bytes(b"de").unwrap();
 
-                    assert_eq!(value, Value::Dict(HashMap::new()));
+                    assert_eq!(value, Value::Dict(BTreeMap::new()));
                 }
             }
 
             mod with_integer_keys {
                 mod one_key {
-                    use std::collections::HashMap;
+                    use std::collections::BTreeMap;
 
                     use serde::Serialize;
 
@@ -674,7 +1119,7 @@ mod tests {
                     fn serialization() {
                         let mut ser = Serializer::new();
 
-                        let value = Value::Dict(HashMap::from([(b"key".to_vec(), Value::Int(3))]));
+                        let value = Value::Dict(BTreeMap::from([(b"key".to_vec(), Value::Int(3))]));
                         let _unused = value.serialize(&mut ser);
 
                         // cspell: disable-next-line
@@ -688,13 +1133,13 @@ mod tests {
 
                         assert_eq!(
                             value,
-                            Value::Dict(HashMap::from([(b"key".to_vec(), Value::Int(3))]))
+                            Value::Dict(BTreeMap::from([(b"key".to_vec(), Value::Int(3))]))
                         );
                     }
                 }
 
                 mod multiple_keys {
-                    use std::collections::HashMap;
+                    use std::collections::BTreeMap;
 
                     use serde::Serialize;
 
@@ -708,7 +1153,7 @@ mod tests {
                     fn serialization() {
                         let mut ser = Serializer::new();
 
-                        let value = Value::Dict(HashMap::from([
+                        let value = Value::Dict(BTreeMap::from([
                             (b"key1".to_vec(), Value::Int(1)),
                             (b"key2".to_vec(), Value::Int(2)),
                         ]));
@@ -725,7 +1170,7 @@ mod tests {
 
                         assert_eq!(
                             value,
-                            Value::Dict(HashMap::from([
+                            Value::Dict(BTreeMap::from([
                                 (b"key1".to_vec(), Value::Int(1)),
                                 (b"key2".to_vec(), Value::Int(2)),
                             ]))
@@ -736,7 +1181,7 @@ mod tests {
 
             mod with_byte_string_keys {
                 mod one_key {
-                    use std::collections::HashMap;
+                    use std::collections::BTreeMap;
 
                     use serde::Serialize;
 
@@ -750,7 +1195,7 @@ mod tests {
                     fn serialization() {
                         let mut ser = Serializer::new();
 
-                        let value = Value::Dict(HashMap::from([(
+                        let value = Value::Dict(BTreeMap::from([(
                             b"key".to_vec(),
                             Value::Bytes(b"spam".to_vec()),
                         )]));
@@ -767,7 +1212,7 @@ mod tests {
 
                         assert_eq!(
                             value,
-                            Value::Dict(HashMap::from([(
+                            Value::Dict(BTreeMap::from([(
                                 b"key".to_vec(),
                                 Value::Bytes(b"spam".to_vec()),
                             )]))
@@ -776,7 +1221,7 @@ mod tests {
                 }
 
                 mod multiple_keys {
-                    use std::collections::HashMap;
+                    use std::collections::BTreeMap;
 
                     use serde::Serialize;
 
@@ -790,7 +1235,7 @@ mod tests {
                     fn serialization() {
                         let mut ser = Serializer::new();
 
-                        let value = Value::Dict(HashMap::from([
+                        let value = Value::Dict(BTreeMap::from([
                             (b"key1".to_vec(), Value::Bytes(b"spam1".to_vec())),
                             (b"key2".to_vec(), Value::Bytes(b"spam2".to_vec())),
                         ]));
@@ -807,7 +1252,7 @@ mod tests {
 
                         assert_eq!(
                             value,
-                            Value::Dict(HashMap::from([
+                            Value::Dict(BTreeMap::from([
                                 (b"key1".to_vec(), Value::Bytes(b"spam1".to_vec())),
                                 (b"key2".to_vec(), Value::Bytes(b"spam2".to_vec())),
                             ]))
@@ -818,7 +1263,7 @@ mod tests {
 
             mod with_list_keys {
                 mod empty {
-                    use std::collections::HashMap;
+                    use std::collections::BTreeMap;
 
                     use serde::Serialize;
 
@@ -832,7 +1277,7 @@ mod tests {
                     fn serialization() {
                         let mut ser = Serializer::new();
 
-                        let value = Value::Dict(HashMap::from([(
+                        let value = Value::Dict(BTreeMap::from([(
                             b"key".to_vec(),
                             Value::List(vec![Value::Int(1)]),
                         )]));
@@ -849,7 +1294,7 @@ mod tests {
 
                         assert_eq!(
                             value,
-                            Value::Dict(HashMap::from([(
+                            Value::Dict(BTreeMap::from([(
                                 b"key".to_vec(),
                                 Value::List(vec![Value::Int(1)]),
                             )]))
@@ -0,0 +1,240 @@
+//! An integer of any size, for the integers that do not fit in an `i64`.
+
+use crate::error::{Error, Result};
+use serde::de::{self, IntoDeserializer as _};
+use serde::ser;
+use std::fmt;
+use std::str::{self, FromStr};
+
+/// Name of the newtype struct through which integers beyond the range of `i128` and `u128` are
+/// passed to this crate's serializer, as a string of their digits. The deserializer passes
+/// integers beyond the range of `i64` and `u64` the same way, except to the 128-bit types.
+pub(crate) const TOKEN: &str = "$serde_bencode::private::BigInt";
+
+/// A bencode integer of any size.
+///
+/// Bencode does not limit the size of integers. Those that fit in an `i64`, `u64`, `i128` or
+/// `u128` are serialized as the first of these that fits, and anything larger as a string of
+/// decimal digits in a newtype struct, which this crate's serializer handles as a bencode
+/// integer. The deserializer hands integers beyond the range of `i64` and `u64` on in the same
+/// newtype struct, which serde can buffer for `#[serde(flatten)]` fields where it cannot buffer
+/// an `i128` or `u128`. Fields of the 128-bit types are still deserialized from any integer that
+/// fits them.
+///
+/// With the `num-bigint` feature, it converts to and from `num_bigint::BigInt` for arithmetic.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::value::{BigInt, Value};
+///
+/// let value: Value = serde_bencode::from_bytes(b"i340282366920938463463374607431768211456e")?;
+/// let expected: BigInt = "340282366920938463463374607431768211456".parse()?;
+///
+/// assert_eq!(value, Value::BigInt(expected));
+/// assert_eq!(
+///     serde_bencode::to_bytes(&value)?,
+///     b"i340282366920938463463374607431768211456e"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BigInt {
+    /// The decimal digits, with a `-` sign if negative and without leading zeros.
+    text: String,
+}
+
+impl BigInt {
+    /// Parses the text of a bencode integer, which may have a sign and leading zeros.
+    pub(crate) fn from_ascii(text: &[u8]) -> Option<Self> {
+        let (negative, digits) = match text {
+            [b'-', digits @ ..] => (true, digits),
+            [b'+', digits @ ..] | digits => (false, digits),
+        };
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let start = digits
+            .iter()
+            .position(|&b| b != b'0')
+            .unwrap_or(digits.len());
+        let digits = &digits[start..];
+        let mut text = String::with_capacity(digits.len() + 1);
+        match (negative, digits) {
+            (_, []) => text.push('0'),
+            (true, _) => text.push('-'),
+            (false, _) => {}
+        }
+        // The digits are all ASCII.
+        text.push_str(str::from_utf8(digits).ok()?);
+        Some(BigInt { text })
+    }
+
+    /// Returns true if the integer is less than zero.
+    #[must_use]
+    pub fn is_negative(&self) -> bool {
+        self.text.starts_with('-')
+    }
+
+    /// Returns the integer as an `i64`, if it fits.
+    #[must_use]
+    pub fn to_i64(&self) -> Option<i64> {
+        self.text.parse().ok()
+    }
+
+    /// Returns the integer as a `u64`, if it fits.
+    #[must_use]
+    pub fn to_u64(&self) -> Option<u64> {
+        self.text.parse().ok()
+    }
+
+    /// Returns the integer as an `i128`, if it fits.
+    #[must_use]
+    pub fn to_i128(&self) -> Option<i128> {
+        self.text.parse().ok()
+    }
+
+    /// Returns the integer as a `u128`, if it fits.
+    #[must_use]
+    pub fn to_u128(&self) -> Option<u128> {
+        self.text.parse().ok()
+    }
+
+    /// Visits the integer with the first of `i64` and `u64` that fits, or as a newtype struct
+    /// holding its digits. Serde buffers the newtype struct for `#[serde(flatten)]` fields and
+    /// internally tagged enums, where it would reject an `i128` or `u128`.
+    pub(crate) fn visit<'de, V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if let Some(i) = self.to_i64() {
+            visitor.visit_i64(i)
+        } else if let Some(u) = self.to_u64() {
+            visitor.visit_u64(u)
+        } else {
+            visitor.visit_newtype_struct(self.text.into_deserializer())
+        }
+    }
+
+    /// Visits the integer like [`BigInt::visit`], but with an `i128` or `u128` if it fits one,
+    /// for deserializing those types.
+    pub(crate) fn visit_wide<'de, V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if let Some(i) = self.to_i64() {
+            visitor.visit_i64(i)
+        } else if let Some(u) = self.to_u64() {
+            visitor.visit_u64(u)
+        } else if let Some(i) = self.to_i128() {
+            visitor.visit_i128(i)
+        } else if let Some(u) = self.to_u128() {
+            visitor.visit_u128(u)
+        } else {
+            visitor.visit_newtype_struct(self.text.into_deserializer())
+        }
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        BigInt::from_ascii(s.as_bytes())
+            .ok_or_else(|| Error::InvalidValue(format!("Can't parse `{s}` as integer")))
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for BigInt {
+                fn from(v: $ty) -> BigInt {
+                    BigInt { text: v.to_string() }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i64 u64 i128 u128);
+
+#[cfg(feature = "num-bigint")]
+impl From<num_bigint::BigInt> for BigInt {
+    fn from(v: num_bigint::BigInt) -> BigInt {
+        BigInt {
+            text: v.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl From<BigInt> for num_bigint::BigInt {
+    fn from(v: BigInt) -> num_bigint::BigInt {
+        num_bigint::BigInt::parse_bytes(v.text.as_bytes(), 10)
+            .unwrap_or_else(|| unreachable!("`{}` is not a valid integer", v.text))
+    }
+}
+
+impl ser::Serialize for BigInt {
+    fn serialize<S: ser::Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        if let Some(i) = self.to_i64() {
+            s.serialize_i64(i)
+        } else if let Some(u) = self.to_u64() {
+            s.serialize_u64(u)
+        } else if let Some(i) = self.to_i128() {
+            s.serialize_i128(i)
+        } else if let Some(u) = self.to_u128() {
+            s.serialize_u128(u)
+        } else {
+            s.serialize_newtype_struct(TOKEN, &self.text)
+        }
+    }
+}
+
+struct BigIntVisitor;
+
+impl<'de> de::Visitor<'de> for BigIntVisitor {
+    type Value = BigInt;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an integer")
+    }
+
+    fn visit_i64<E>(self, value: i64) -> std::result::Result<BigInt, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u64<E>(self, value: u64) -> std::result::Result<BigInt, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i128<E>(self, value: i128) -> std::result::Result<BigInt, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u128<E>(self, value: u128) -> std::result::Result<BigInt, E> {
+        Ok(value.into())
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<BigInt, E> {
+        BigInt::from_ascii(value.as_bytes())
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<BigInt, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for BigInt {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(BigIntVisitor)
+    }
+}
@@ -0,0 +1,445 @@
+//! Deserialize a Rust data structure from a [`Value`].
+
+use super::Value;
+use crate::de::{visit_bytes_or_str, MapKeyDeserializer};
+use crate::error::{Error, Result};
+use crate::raw;
+use serde::de::{self, Error as _, IntoDeserializer, Unexpected};
+use serde::forward_to_deserialize_any;
+use std::borrow::Cow;
+use std::str;
+
+impl Value {
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Value::Int(i) => Unexpected::Signed(*i),
+            Value::BigInt(_) => Unexpected::Other("integer"),
+            Value::Bytes(bytes) => Unexpected::Bytes(bytes),
+            Value::List(_) => Unexpected::Seq,
+            Value::Dict(_) => Unexpected::Map,
+            Value::Null => Unexpected::Unit,
+        }
+    }
+
+    fn invalid_type(&self, expected: &str) -> Error {
+        Error::invalid_type(self.unexpected(), &expected)
+    }
+
+    /// Interprets the integers `0` and `1` as booleans.
+    fn to_bool(&self) -> Result<bool> {
+        match self {
+            Value::Int(0) => Ok(false),
+            Value::Int(1) => Ok(true),
+            Value::Int(i) => Err(Error::invalid_value(Unexpected::Signed(*i), &"0 or 1")),
+            _ => Err(self.invalid_type("0 or 1")),
+        }
+    }
+}
+
+/// Gives access to the elements of a [`Value::List`].
+struct ListAccess<I> {
+    iter: I,
+}
+
+impl<'de, I> de::SeqAccess<'de> for ListAccess<I>
+where
+    I: ExactSizeIterator,
+    I::Item: de::Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        self.iter.next().map(|v| seed.deserialize(v)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Gives access to the entries of a [`Value::Dict`].
+struct DictAccess<'de, I, V> {
+    iter: I,
+    value: Option<V>,
+    key: Cow<'de, [u8]>,
+}
+
+impl<I, V> DictAccess<'_, I, V> {
+    fn new(iter: I) -> Self {
+        DictAccess {
+            iter,
+            value: None,
+            key: Cow::Borrowed(&[]),
+        }
+    }
+}
+
+impl<'de, I, V> de::MapAccess<'de> for DictAccess<'de, I, V>
+where
+    I: ExactSizeIterator<Item = (Cow<'de, [u8]>, V)>,
+    V: de::Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let Some((key, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+        self.key = key;
+        self.value = Some(value);
+        seed.deserialize(MapKeyDeserializer { key: &self.key })
+            .map(Some)
+    }
+
+    fn next_value_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value> {
+        let value = self.value.take().ok_or_else(|| {
+            Error::InvalidValue("`next_value` called before `next_key`".to_string())
+        })?;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Gives access to an enum variant: either a byte string naming a unit variant, or a dictionary
+/// with a single entry mapping the variant name to its contents.
+struct VariantAccess<'de, V> {
+    variant: Cow<'de, [u8]>,
+    value: Option<V>,
+}
+
+impl<'de, V: de::Deserializer<'de, Error = Error>> de::EnumAccess<'de> for VariantAccess<'de, V> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<S: de::DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Self)> {
+        let variant = seed.deserialize(MapKeyDeserializer { key: &self.variant })?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, V: de::Deserializer<'de, Error = Error>> de::VariantAccess<'de>
+    for VariantAccess<'de, V>
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.value {
+            None => Ok(()),
+            Some(value) => de::Deserialize::deserialize(value),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        match self.value {
+            Some(value) => seed.deserialize(value),
+            None => Err(Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<W: de::Visitor<'de>>(self, len: usize, visitor: W) -> Result<W::Value> {
+        match self.value {
+            Some(value) => value.deserialize_tuple(len, visitor),
+            None => Err(Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<W: de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: W,
+    ) -> Result<W::Value> {
+        match self.value {
+            Some(value) => value.deserialize_map(visitor),
+            None => Err(Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Int(i) => visitor.visit_i64(i),
+            Value::BigInt(i) => i.visit(visitor),
+            Value::Bytes(bytes) => visit_bytes_or_str(Cow::Owned(bytes), visitor),
+            Value::List(list) => visitor.visit_seq(ListAccess {
+                iter: list.into_iter(),
+            }),
+            Value::Dict(dict) => visitor.visit_map(DictAccess::new(
+                dict.into_iter().map(|(k, v)| (Cow::Owned(k), v)),
+            )),
+            Value::Null => visitor.visit_unit(),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 unit seq map unit_struct tuple tuple_struct
+        ignored_any struct
+    }
+
+    fn deserialize_i128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::BigInt(i) => i.visit_wide(visitor),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_i128(visitor)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            _ => Err(self.invalid_type("bytes")),
+        }
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == raw::TOKEN {
+            return visitor.visit_byte_buf(crate::to_bytes(&self)?);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    #[inline]
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_bool(self.to_bool()?)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::Bytes(variant) => visitor.visit_enum(VariantAccess::<Value> {
+                variant: Cow::Owned(variant),
+                value: None,
+            }),
+            Value::Dict(dict) if dict.len() == 1 => {
+                let (variant, value) = dict.into_iter().next().unwrap();
+                visitor.visit_enum(VariantAccess {
+                    variant: Cow::Owned(variant),
+                    value: Some(value),
+                })
+            }
+            _ => Err(self.invalid_type("bytes or a dict with one entry")),
+        }
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => {
+                let s = String::from_utf8(bytes).map_err(|e| {
+                    Error::invalid_value(Unexpected::Bytes(e.as_bytes()), &"utf-8 string")
+                })?;
+                visitor.visit_string(s)
+            }
+            _ => Err(self.invalid_type("bytes")),
+        }
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de Value {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Int(i) => visitor.visit_i64(*i),
+            Value::BigInt(i) => i.clone().visit(visitor),
+            Value::Bytes(bytes) => visit_bytes_or_str(Cow::Borrowed(bytes), visitor),
+            Value::List(list) => visitor.visit_seq(ListAccess { iter: list.iter() }),
+            Value::Dict(dict) => visitor.visit_map(DictAccess::new(
+                dict.iter().map(|(k, v)| (Cow::Borrowed(k.as_slice()), v)),
+            )),
+            Value::Null => visitor.visit_unit(),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 unit seq map unit_struct tuple tuple_struct
+        ignored_any struct
+    }
+
+    fn deserialize_i128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::BigInt(i) => i.clone().visit_wide(visitor),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u128<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_i128(visitor)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => visitor.visit_borrowed_bytes(bytes),
+            _ => Err(self.invalid_type("bytes")),
+        }
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == raw::TOKEN {
+            return visitor.visit_byte_buf(crate::to_bytes(self)?);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    #[inline]
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_bool(self.to_bool()?)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Value::Bytes(variant) => visitor.visit_enum(VariantAccess::<&Value> {
+                variant: Cow::Borrowed(variant),
+                value: None,
+            }),
+            Value::Dict(dict) if dict.len() == 1 => {
+                let (variant, value) = dict.iter().next().unwrap();
+                visitor.visit_enum(VariantAccess {
+                    variant: Cow::Borrowed(variant),
+                    value: Some(value),
+                })
+            }
+            _ => Err(self.invalid_type("bytes or a dict with one entry")),
+        }
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::Bytes(bytes) => {
+                let s = str::from_utf8(bytes)
+                    .map_err(|_| Error::invalid_value(Unexpected::Bytes(bytes), &"utf-8 string"))?;
+                visitor.visit_borrowed_str(s)
+            }
+            _ => Err(self.invalid_type("bytes")),
+        }
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+}
+
+impl IntoDeserializer<'_, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Interpret a [`Value`] as an instance of type `T`, without going through bencoded bytes.
+///
+/// To borrow byte strings from the value instead, deserialize from a `&Value` directly with
+/// `T::deserialize(&value)`.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::value::Value;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize, PartialEq, Eq, Debug)]
+/// struct Ping {
+///     y: String,
+///     q: String,
+/// }
+///
+/// // cspell:disable-next-line
+/// let value: Value = serde_bencode::from_bytes(b"d1:q4:ping1:y1:qe")?;
+/// if value["y"].as_str() == Some("q") {
+///     let ping: Ping = serde_bencode::from_value(value)?;
+///     assert_eq!(ping.q, "ping");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This conversion can fail if the structure of the value does not match the structure expected
+/// by `T`. It can also fail if `T`'s implementation of `Deserialize` decides to fail.
+pub fn from_value<T: de::DeserializeOwned>(value: Value) -> Result<T> {
+    T::deserialize(value)
+}
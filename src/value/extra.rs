@@ -0,0 +1,170 @@
+//! A catch-all for the dictionary entries a struct does not know about.
+
+use super::Value;
+use serde::de;
+use serde::ser::{self, SerializeMap as _};
+use serde_bytes::{ByteBuf, Bytes};
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The entries of a dictionary that a struct has no field for, kept so that they are written
+/// back when the struct is serialized again.
+///
+/// Add it to a struct as a `#[serde(flatten)]` field. Deserializing collects every entry that no
+/// other field claims, and serializing merges them back with the other fields in sorted order,
+/// so a dictionary in canonical form re-encodes to the same bytes. This keeps vendor keys, and
+/// the info hash of a torrent whose `info` dictionary has keys the struct does not model.
+///
+/// Entries are collected by serde's support for `#[serde(flatten)]`, which keeps the other
+/// fields of the struct from being booleans (see the
+/// [crate documentation](crate#flattened-fields-and-internally-tagged-enums)).
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::value::{Extra, Value};
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Torrent {
+///     announce: String,
+///     #[serde(flatten)]
+///     extra: Extra,
+/// }
+///
+/// // cspell:disable-next-line
+/// let b = b"d8:announce3:url7:comment5:hello4:infod4:name1:aee";
+/// let mut torrent: Torrent = serde_bencode::from_bytes(b)?;
+/// assert_eq!(torrent.extra.get(b"comment"), Some(&Value::from("hello")));
+///
+/// torrent.announce = "new".to_string();
+/// // cspell:disable-next-line
+/// let expected = b"d8:announce3:new7:comment5:hello4:infod4:name1:aee";
+/// assert_eq!(serde_bencode::to_bytes(&torrent)?, expected);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Extra {
+    entries: BTreeMap<Vec<u8>, Value>,
+}
+
+impl Extra {
+    /// Creates an empty set of entries.
+    #[must_use]
+    pub fn new() -> Extra {
+        Extra::default()
+    }
+
+    /// Returns the value of the entry with the given key.
+    #[must_use]
+    pub fn get(&self, key: &[u8]) -> Option<&Value> {
+        self.entries.get(key)
+    }
+
+    /// Returns a mutable reference to the value of the entry with the given key.
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut Value> {
+        self.entries.get_mut(key)
+    }
+
+    /// Adds an entry, returning the value it replaces.
+    ///
+    /// An entry whose key is also the name of a field of the struct makes serializing the struct
+    /// fail with [`Error::DuplicateKey`](crate::Error::DuplicateKey).
+    pub fn insert<K: Into<Vec<u8>>, V: Into<Value>>(&mut self, key: K, value: V) -> Option<Value> {
+        self.entries.insert(key.into(), value.into())
+    }
+
+    /// Removes the entry with the given key, returning its value.
+    pub fn remove(&mut self, key: &[u8]) -> Option<Value> {
+        self.entries.remove(key)
+    }
+
+    /// Returns the number of entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the entries, sorted by key.
+    pub fn iter(&self) -> btree_map::Iter<'_, Vec<u8>, Value> {
+        self.entries.iter()
+    }
+}
+
+impl From<BTreeMap<Vec<u8>, Value>> for Extra {
+    fn from(entries: BTreeMap<Vec<u8>, Value>) -> Extra {
+        Extra { entries }
+    }
+}
+
+impl From<Extra> for BTreeMap<Vec<u8>, Value> {
+    fn from(extra: Extra) -> BTreeMap<Vec<u8>, Value> {
+        extra.entries
+    }
+}
+
+impl From<Extra> for Value {
+    fn from(extra: Extra) -> Value {
+        Value::Dict(extra.entries)
+    }
+}
+
+impl IntoIterator for Extra {
+    type Item = (Vec<u8>, Value);
+    type IntoIter = btree_map::IntoIter<Vec<u8>, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Extra {
+    type Item = (&'a Vec<u8>, &'a Value);
+    type IntoIter = btree_map::Iter<'a, Vec<u8>, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl ser::Serialize for Extra {
+    fn serialize<S: ser::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut map = s.serialize_map(Some(self.entries.len()))?;
+        for (k, v) in &self.entries {
+            map.serialize_entry(Bytes::new(k), v)?;
+        }
+        map.end()
+    }
+}
+
+struct ExtraVisitor;
+
+impl<'de> de::Visitor<'de> for ExtraVisitor {
+    type Value = Extra;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a dictionary")
+    }
+
+    fn visit_map<V: de::MapAccess<'de>>(self, mut access: V) -> Result<Extra, V::Error> {
+        let mut entries = BTreeMap::new();
+        while let Some((k, v)) = access.next_entry::<ByteBuf, Value>()? {
+            entries.insert(k.into_vec(), v);
+        }
+        Ok(Extra { entries })
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Extra {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Extra, D::Error> {
+        deserializer.deserialize_map(ExtraVisitor)
+    }
+}
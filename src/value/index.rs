@@ -0,0 +1,123 @@
+//! Indexing into bencoded values.
+
+use super::Value;
+use std::ops;
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for usize {}
+    impl Sealed for str {}
+    impl Sealed for String {}
+    impl Sealed for [u8] {}
+    impl Sealed for Vec<u8> {}
+    impl<T: ?Sized + Sealed> Sealed for &T {}
+}
+
+/// A type that can be used to index into a [`Value`]: list indices (`usize`) and dictionary
+/// keys (`str`, `String`, `[u8]` and `Vec<u8>`).
+///
+/// This trait is sealed and cannot be implemented for types outside of `serde_bencode`.
+pub trait Index: private::Sealed {
+    #[doc(hidden)]
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value>;
+
+    #[doc(hidden)]
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value>;
+}
+
+impl Index for usize {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match v {
+            Value::List(list) => list.get(*self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        match v {
+            Value::List(list) => list.get_mut(*self),
+            _ => None,
+        }
+    }
+}
+
+impl Index for [u8] {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match v {
+            Value::Dict(dict) => dict.get(self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        match v {
+            Value::Dict(dict) => dict.get_mut(self),
+            _ => None,
+        }
+    }
+}
+
+impl Index for Vec<u8> {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        self.as_slice().index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        self.as_slice().index_into_mut(v)
+    }
+}
+
+impl Index for str {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        self.as_bytes().index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        self.as_bytes().index_into_mut(v)
+    }
+}
+
+impl Index for String {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        self.as_bytes().index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        self.as_bytes().index_into_mut(v)
+    }
+}
+
+impl<T: ?Sized + Index> Index for &T {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).index_into_mut(v)
+    }
+}
+
+/// Returned when indexing a value with a missing key or index.
+static NULL: Value = Value::Null;
+
+impl<I: Index> ops::Index<I> for Value {
+    type Output = Value;
+
+    /// Index into a bencoded list or dictionary.
+    ///
+    /// Unlike [`Value::get`], this does not fail when the key or index is missing or the value
+    /// has the wrong type. It returns [`Value::Null`] instead, for which every accessor returns
+    /// `None`, so lookups can be chained:
+    ///
+    /// ```
+    /// # use serde_bencode::value::Value;
+    /// // cspell:disable-next-line
+    /// let value: Value = serde_bencode::from_bytes(b"d4:infod5:filesld6:lengthi8eeeee").unwrap();
+    ///
+    /// assert_eq!(value["info"]["files"][0]["length"].as_int(), Some(8));
+    /// assert!(value["info"]["files"][1]["length"].is_null());
+    /// ```
+    fn index(&self, index: I) -> &Value {
+        index.index_into(self).unwrap_or(&NULL)
+    }
+}
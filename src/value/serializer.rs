@@ -0,0 +1,370 @@
+//! Serialize a Rust data structure into a [`Value`].
+
+use super::{BigInt, Value, BIG_INT_TOKEN};
+use crate::error::{Error, Result};
+use crate::raw;
+use crate::ser::string;
+use serde::ser;
+use std::collections::BTreeMap;
+
+/// A serializer that builds a [`Value`] instead of writing bencode.
+///
+/// Serializing returns `None` for unit and `None`, which have no bencode representation. As with
+/// [`crate::Serializer`], they are left out of lists and dictionaries.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Serializer;
+
+#[doc(hidden)]
+// todo: This should be pub(crate).
+pub struct SerializeList {
+    list: Vec<Value>,
+    /// The variant name, for tuple variants.
+    variant: Option<&'static str>,
+}
+
+impl SerializeList {
+    fn push<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
+        if let Some(value) = value.serialize(Serializer)? {
+            self.list.push(value);
+        }
+        Ok(())
+    }
+
+    fn end_list(self) -> Value {
+        let list = Value::List(self.list);
+        match self.variant {
+            Some(variant) => variant_dict(variant, list),
+            None => list,
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+    fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(self.end_list()))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+    fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(self.end_list()))
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+    fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(self.end_list()))
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = Option<Value>;
+    type Error = Error;
+    fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(self.end_list()))
+    }
+}
+
+#[doc(hidden)]
+// todo: This should be pub(crate).
+pub struct SerializeDict {
+    dict: BTreeMap<Vec<u8>, Value>,
+    /// The variant name, for struct variants.
+    variant: Option<&'static str>,
+    cur_key: Option<Vec<u8>>,
+}
+
+impl SerializeDict {
+    fn insert<T: ?Sized + ser::Serialize>(&mut self, key: Vec<u8>, value: &T) -> Result<()> {
+        if let Some(value) = value.serialize(Serializer)? {
+            self.dict.insert(key, value);
+        }
+        Ok(())
+    }
+
+    fn end_dict(self) -> Result<Option<Value>> {
+        if self.cur_key.is_some() {
+            return Err(Error::InvalidValue(
+                "`serialize_key` called without calling  `serialize_value`".to_string(),
+            ));
+        }
+        let dict = Value::Dict(self.dict);
+        Ok(Some(match self.variant {
+            Some(variant) => variant_dict(variant, dict),
+            None => dict,
+        }))
+    }
+}
+
+impl ser::SerializeMap for SerializeDict {
+    type Ok = Option<Value>;
+    type Error = Error;
+    fn serialize_key<T: ?Sized + ser::Serialize>(&mut self, key: &T) -> Result<()> {
+        if self.cur_key.is_some() {
+            return Err(Error::InvalidValue(
+                "`serialize_key` called multiple times without calling  `serialize_value`"
+                    .to_string(),
+            ));
+        }
+        self.cur_key = Some(key.serialize(&mut string::Serializer)?);
+        Ok(())
+    }
+    fn serialize_value<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<()> {
+        let key = self.cur_key.take().ok_or_else(|| {
+            Error::InvalidValue(
+                "`serialize_value` called without calling `serialize_key`".to_string(),
+            )
+        })?;
+        self.insert(key, value)
+    }
+    fn end(self) -> Result<Option<Value>> {
+        self.end_dict()
+    }
+}
+
+impl ser::SerializeStruct for SerializeDict {
+    type Ok = Option<Value>;
+    type Error = Error;
+    fn serialize_field<T: ?Sized + ser::Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.into(), value)
+    }
+    fn end(self) -> Result<Option<Value>> {
+        self.end_dict()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeDict {
+    type Ok = Option<Value>;
+    type Error = Error;
+    fn serialize_field<T: ?Sized + ser::Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.into(), value)
+    }
+    fn end(self) -> Result<Option<Value>> {
+        self.end_dict()
+    }
+}
+
+/// Wraps the value of an enum variant in a dictionary keyed by the variant name.
+fn variant_dict(variant: &str, value: Value) -> Value {
+    Value::Dict(BTreeMap::from([(variant.into(), value)]))
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeDict;
+    type SerializeStruct = SerializeDict;
+    type SerializeStructVariant = SerializeDict;
+
+    fn serialize_bool(self, value: bool) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(value))
+    }
+    fn serialize_i8(self, value: i8) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(value))
+    }
+    fn serialize_i16(self, value: i16) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(value))
+    }
+    fn serialize_i32(self, value: i32) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(value))
+    }
+    fn serialize_i64(self, value: i64) -> Result<Option<Value>> {
+        Ok(Some(Value::Int(value)))
+    }
+    fn serialize_i128(self, value: i128) -> Result<Option<Value>> {
+        Ok(Some(value.into()))
+    }
+    fn serialize_u8(self, value: u8) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(value))
+    }
+    fn serialize_u16(self, value: u16) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(value))
+    }
+    fn serialize_u32(self, value: u32) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(value))
+    }
+    fn serialize_u64(self, value: u64) -> Result<Option<Value>> {
+        Ok(Some(value.into()))
+    }
+    fn serialize_u128(self, value: u128) -> Result<Option<Value>> {
+        Ok(Some(value.into()))
+    }
+    fn serialize_f32(self, _value: f32) -> Result<Option<Value>> {
+        Err(Error::InvalidValue("Cannot serialize f32".to_string()))
+    }
+    fn serialize_f64(self, _value: f64) -> Result<Option<Value>> {
+        Err(Error::InvalidValue("Cannot serialize f64".to_string()))
+    }
+    fn serialize_char(self, value: char) -> Result<Option<Value>> {
+        let mut buffer = [0; 4];
+        self.serialize_bytes(value.encode_utf8(&mut buffer).as_bytes())
+    }
+    fn serialize_str(self, value: &str) -> Result<Option<Value>> {
+        self.serialize_bytes(value.as_bytes())
+    }
+    fn serialize_bytes(self, value: &[u8]) -> Result<Option<Value>> {
+        Ok(Some(Value::Bytes(value.to_vec())))
+    }
+    fn serialize_unit(self) -> Result<Option<Value>> {
+        Ok(None)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<Value>> {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Option<Value>> {
+        self.serialize_str(variant)
+    }
+    fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Option<Value>> {
+        if name == raw::TOKEN {
+            let raw = value.serialize(&mut string::Serializer)?;
+            return crate::from_bytes(&raw).map(Some);
+        }
+        if name == BIG_INT_TOKEN {
+            let digits = value.serialize(&mut string::Serializer)?;
+            let int = BigInt::from_ascii(&digits).ok_or_else(|| {
+                Error::InvalidValue(format!(
+                    "Can't parse `{}` as integer",
+                    String::from_utf8_lossy(&digits)
+                ))
+            })?;
+            return Ok(Some(int.into()));
+        }
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Option<Value>> {
+        let value = value.serialize(self)?.ok_or_else(|| {
+            Error::InvalidValue(format!("Variant `{variant}` has no value to serialize"))
+        })?;
+        Ok(Some(variant_dict(variant, value)))
+    }
+    fn serialize_none(self) -> Result<Option<Value>> {
+        Ok(None)
+    }
+    fn serialize_some<T: ?Sized + ser::Serialize>(self, value: &T) -> Result<Option<Value>> {
+        value.serialize(self)
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList> {
+        Ok(SerializeList {
+            list: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+    fn serialize_tuple(self, size: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(size))
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList> {
+        Ok(SerializeList {
+            list: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeDict> {
+        Ok(SerializeDict {
+            dict: BTreeMap::new(),
+            variant: None,
+            cur_key: None,
+        })
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeDict> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeDict> {
+        Ok(SerializeDict {
+            dict: BTreeMap::new(),
+            variant: Some(variant),
+            cur_key: None,
+        })
+    }
+}
+
+/// Convert a `T` into a [`Value`], without going through bencoded bytes.
+///
+/// # Examples
+/// ```
+/// # fn main() -> Result<(), serde_bencode::Error> {
+/// use serde_bencode::value::Value;
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Ping<'a> {
+///     y: &'a str,
+///     q: &'a str,
+/// }
+///
+/// let value = serde_bencode::to_value(&Ping { y: "q", q: "ping" })?;
+///
+/// assert_eq!(value["q"], Value::from("ping"));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This conversion can fail if `T`'s implementation of `Serialize` decides to fail, if `T`
+/// contains a type bencode cannot represent, such as floats, or if `T` serializes to nothing at
+/// all, like `()` or `None`.
+pub fn to_value<T: ?Sized + ser::Serialize>(value: &T) -> Result<Value> {
+    value
+        .serialize(Serializer)?
+        .ok_or_else(|| Error::InvalidValue("Value serialized to nothing".to_string()))
+}